@@ -0,0 +1,170 @@
+//! Compares the classic Runge-Kutta method with the original implementation,
+//! which allocated a fresh vector for every stage and every intermediate sum.
+//! The original `get_ks` and `step` are copied below, they only took right-hand sides
+//! in the per-component form, so the current implementation is timed with that form as well
+//! as with a vector-valued right-hand side.
+//! Run with `cargo run --release --example bench_runge_kutta`.
+use ngdl_rust::definitions::{
+    BoxedFunction, InitialValueSystemProblem, PointwiseAdd, SampleableFunction, ScalarMul,
+};
+use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+use ngdl_rust::util::make_zero_vec;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// Number of grid points of the heat equation.
+/// Small, as the per-component form copies the whole state for every component.
+const DIM: usize = 1_000;
+const DX: f64 = 1.0 / (DIM + 1) as f64;
+const H: f64 = 1e-7;
+const T_TARGET: f64 = 2e-5;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let (original, original_time) = timed(|| original_rk4(&components(), H, T_TARGET));
+    let (per_component, per_component_time) = timed(|| {
+        let problem = InitialValueSystemProblem::new(0.0, start_values(), components());
+        make_classic_runge_kutta(problem, H).try_value_at(T_TARGET)
+    });
+    let per_component = per_component?;
+    let (vector, vector_time) = timed(|| {
+        let problem = InitialValueSystemProblem::new(0.0, start_values(), heat_equation);
+        make_classic_runge_kutta(problem, H).try_value_at(T_TARGET)
+    });
+    let vector = vector?;
+
+    let difference = |values: &[f64]| {
+        values
+            .iter()
+            .zip(&original)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max)
+    };
+    println!(
+        "{} components, {} steps",
+        DIM,
+        (T_TARGET / H).round() as usize
+    );
+    println!("Original:                  {:?}", original_time);
+    for (name, time, values) in [
+        (
+            "Per-component right-hand side",
+            per_component_time,
+            &per_component,
+        ),
+        ("Vector-valued right-hand side", vector_time, &vector),
+    ] {
+        println!(
+            "{}: {:?}, speedup {:.2}, largest difference {:e}",
+            name,
+            time,
+            original_time.as_secs_f64() / time.as_secs_f64(),
+            difference(values)
+        );
+    }
+    Ok(())
+}
+
+fn timed<R>(run: impl FnOnce() -> R) -> (R, Duration) {
+    let start = Instant::now();
+    let result = run();
+    (result, start.elapsed())
+}
+
+/// sin(pi x) on the inner grid points.
+fn start_values() -> Vec<f64> {
+    (1..=DIM)
+        .map(|i| (std::f64::consts::PI * i as f64 * DX).sin())
+        .collect()
+}
+
+/// The heat equation u_t = u_xx on [0, 1] with u = 0 on the boundary, discretized in space.
+fn heat_equation(_t: f64, u: &[f64], dudt: &mut [f64]) {
+    for (i, d) in dudt.iter_mut().enumerate() {
+        *d = second_difference(u, i);
+    }
+}
+
+/// The same system with one function per component.
+fn components() -> Vec<BoxedFunction<(f64, Vec<f64>)>> {
+    (0..DIM)
+        .map(|i| {
+            let df: BoxedFunction<(f64, Vec<f64>)> =
+                Box::new(move |(_, u): (f64, Vec<f64>)| second_difference(&u, i));
+            df
+        })
+        .collect()
+}
+
+fn second_difference(u: &[f64], i: usize) -> f64 {
+    let left = if i > 0 { u[i - 1] } else { 0.0 };
+    let right = if i + 1 < DIM { u[i + 1] } else { 0.0 };
+    (left - 2.0 * u[i] + right) / (DX * DX)
+}
+
+/// Classic Runge-Kutta as in the original `OneStepMethod::interval`, only keeping the last value.
+fn original_rk4<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
+    dfs: &[FT],
+    h: f64,
+    t_target: f64,
+) -> Vec<f64> {
+    let mut t = 0.0;
+    let mut values = start_values();
+    while t + h < t_target {
+        values = original_step(dfs, t, &values, h);
+        t += h;
+    }
+    original_step(dfs, t, &values, t_target - t)
+}
+
+const CS: [f64; 4] = [0.0, 0.5, 0.5, 1.0];
+const BS: [f64; 4] = [1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0];
+const COEFFS: [&[f64]; 4] = [&[], &[0.5], &[0.0, 0.5], &[0.0, 0.0, 1.0]];
+
+fn original_get_ks<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
+    dfs: &[FT],
+    t: f64,
+    last_values: &[f64],
+    h: f64,
+) -> Vec<Vec<f64>> {
+    let mut ks: Vec<Vec<f64>> = Vec::with_capacity(CS.len());
+
+    for (idx, c) in CS.iter().enumerate() {
+        // We currently calculate k_idx
+        let t_sample = t + h * *c;
+        let mut sample_vals: Vec<f64> = make_zero_vec(dfs.len());
+        // For the current row take all as that are below the diagonal
+        for (idx_inner, a) in COEFFS[idx].iter().enumerate().take(idx) {
+            // get the values in k_idx_inner, multiply with a and sum up
+            sample_vals = sample_vals.pointwise_add(ks[idx_inner].clone().scalar_mul(*a));
+        }
+        sample_vals = sample_vals
+            .scalar_mul(h)
+            .pointwise_add(last_values.to_vec());
+
+        ks.push(
+            dfs.iter()
+                .map(|f| f.value_at((t_sample, sample_vals.clone())))
+                .collect(),
+        );
+    }
+
+    ks
+}
+
+fn original_step<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
+    dfs: &[FT],
+    t: f64,
+    last_values: &[f64],
+    h: f64,
+) -> Vec<f64> {
+    let ks: Vec<Vec<f64>> = original_get_ks(dfs, t, last_values, h);
+    let change_term: Vec<f64> = BS
+        .iter()
+        .zip(ks)
+        .map(|(b, k)| k.scalar_mul(*b))
+        .fold(make_zero_vec(dfs.len()), |v1, v2| v1.pointwise_add(v2));
+
+    change_term
+        .scalar_mul(h)
+        .pointwise_add(last_values.to_vec())
+}
@@ -9,7 +9,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Only three samples is kinda boring, if we use a computer anyway...
     let hs: Vec<f64> = (1..=15).map(|n| 1.0 / ((2.0f64).powi(n))).collect();
 
-    dbg!(explicit_euler_test_run(ivp, &hs, 0.5));
+    dbg!(explicit_euler_test_run(ivp, &hs, 0.5)?);
 
     Ok(())
 }
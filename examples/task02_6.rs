@@ -24,7 +24,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Only three samples is kinda boring, if we use a computer anyway...
     let hs: Vec<f64> = ks.clone().map(|n| 1.0 / ((2.0f64).powi(n))).collect();
 
-    let approximations = explicit_euler_test_run(ivp, &hs, 0.5);
+    let approximations = explicit_euler_test_run(ivp, &hs, 0.5)?;
 
     let abs_errors: Vec<f64> = approximations.iter().map(|x| abs!(exact - *x)).collect();
 
@@ -42,8 +42,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let hs: Vec<f64> = vec![1.0, 0.1, 0.01, 0.001, 0.00199, 0.002, 0.0021];
 
-    let approximations_explicit = explicit_euler_interval_test_run(ivp, &hs, t_target, 0);
-    let approximations_implicit = implicit_euler_interval_test_run(ivp_implicit, &hs, t_target, 0);
+    let approximations_explicit = explicit_euler_interval_test_run(ivp, &hs, t_target, 0)?;
+    let approximations_implicit = implicit_euler_interval_test_run(ivp_implicit, &hs, t_target, 0)?;
 
     let explicit_errors: Vec<Vec<Point2D>> = approximations_explicit
         .iter()
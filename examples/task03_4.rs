@@ -30,7 +30,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         InitialValueProblem::new(0.0, 1.0, |(_, x)| powf!(x, 1.5));
     let h = 0.2;
 
-    let approximation = explicit_euler_interval(ivp, h, 1.0, 0);
+    let approximation = explicit_euler_interval(ivp, h, 1.0, 0)?;
 
     let mut fg = Figure::new();
     let axis = fg.axes2d().set_legend(Graph(0.3), Graph(1.0), &[], &[]);
@@ -1,6 +1,6 @@
 use ngdl_rust::definitions::{
-    Function, Function1D, FunctionND, InitialValueSystemProblem, Interval, MultSampleableFunction,
-    PointwiseSub,
+    Function, Function1D, FunctionND, FunctionSystem, InitialValueSystemProblem, Interval,
+    MultSampleableFunction, PointwiseSub,
 };
 use ngdl_rust::euler_explicit::{explicit_euler_system, get_residual_function};
 use ngdl_rust::exp;
@@ -17,7 +17,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let exact: FunctionND<f64, Vec<f64>> =
         |t| vec![0.5 * (exp!(-2.0 * t) + 1.0), 0.5 * (-exp!(-2.0 * t) + 1.0)];
 
-    let solution = explicit_euler_system(&problem, h, t_target);
+    let solution = explicit_euler_system(&problem, h, t_target)?;
 
     println!("Problem from task 4, subtask 2\n");
     println!("Step size: h = {}", h);
@@ -68,7 +68,7 @@ fn evaluate_first_error_bound(h: f64) {
     println!("First error bound: {}", error_bound);
 }
 
-fn create_problem() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
+fn create_problem() -> InitialValueSystemProblem<FunctionSystem> {
     let dfx: Function<(f64, Vec<f64>)> = |(_, v)| -v[0] + v[1];
     let dfy: Function<(f64, Vec<f64>)> = |(_, v)| v[0] - v[1];
     InitialValueSystemProblem::new(0.0, vec![1.0, 0.0], vec![dfx, dfy])
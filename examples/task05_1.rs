@@ -34,11 +34,11 @@ fn main() -> Result<(), Box<dyn Error>> {
     let ddy: Function2D = |(x, y)| 1.0 + 2.0 * x + 4.0 * y;
 
     let ivp: InitialValueProblem<Function2D> = InitialValueProblem::new(1.0, 0.5, dy);
-    let approximation = explicit_euler_interval(ivp, h, t_target, 0);
+    let approximation = explicit_euler_interval(ivp, h, t_target, 0)?;
 
     let ivp2: InitialValueProblem<SimpleDifferentiableFunction2D> =
         InitialValueProblem::new(1.0, 0.5, SimpleDifferentiableFunction::new(dy, ddy));
-    let approximation2 = modified_explicit_euler_interval(ivp2, h, t_target, 0);
+    let approximation2 = modified_explicit_euler_interval(ivp2, h, t_target, 0)?;
 
     let mut fg = Figure::new();
     let axis = fg.axes2d().set_legend(Graph(0.5), Graph(1.0), &[], &[]);
@@ -77,11 +77,11 @@ fn main() -> Result<(), Box<dyn Error>> {
     let normal_euler_results: Vec<f64> = hs
         .iter()
         .map(|i| explicit_euler(ivp, *i, t_target))
-        .collect();
+        .collect::<Result<_, _>>()?;
     let modified_euler_results: Vec<f64> = hs
         .iter()
         .map(|i| modified_explicit_euler(ivp2, *i, t_target))
-        .collect();
+        .collect::<Result<_, _>>()?;
 
     let normal_abs_error: Vec<f64> = normal_euler_results
         .iter()
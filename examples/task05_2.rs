@@ -1,7 +1,7 @@
 use gnuplot::Figure;
 use gnuplot::PlotOption::{Caption, Color};
 use itertools::izip;
-use ngdl_rust::definitions::{ClosureSampleableFunction, InitialValueSystemProblem, Point2D};
+use ngdl_rust::definitions::{InitialValueSystemProblem, OdeSystem, Point2D, SolverError};
 use ngdl_rust::euler_explicit::explicit_euler_system_interval;
 use ngdl_rust::plot_util::plot_line_on;
 use ngdl_rust::{cos, sin};
@@ -23,38 +23,36 @@ fn main() -> Result<(), Box<dyn Error>> {
     let hs = vec![0.0001, 0.1, 0.0001, 0.1];
 
     for (k, h, s) in izip!(ks, hs, curve_lengths) {
-        evaluate(k, h, s);
+        evaluate(k, h, s)?;
     }
 
     Ok(())
 }
 
 /// v = vec![r, z, angle]
-fn create_problem(
-    ks: f64,
-) -> InitialValueSystemProblem<ClosureSampleableFunction<(f64, Vec<f64>), f64>> {
-    let dfr: ClosureSampleableFunction<(f64, Vec<f64>), f64> =
-        ClosureSampleableFunction::new(ks, |(_, v), _| cos!(v[2]));
-    let dfz: ClosureSampleableFunction<(f64, Vec<f64>), f64> =
-        ClosureSampleableFunction::new(ks, |(_, v), _| sin!(v[2]));
-    let dfangle: ClosureSampleableFunction<(f64, Vec<f64>), f64> =
-        ClosureSampleableFunction::new(ks, |(_, v), k| {
-            if v[0] > 0.0 {
+fn create_problem(k: f64) -> InitialValueSystemProblem<impl OdeSystem + Clone> {
+    InitialValueSystemProblem::new(
+        0.0,
+        vec![0.0, -1.0, 0.0],
+        move |_t: f64, v: &[f64], dv: &mut [f64]| {
+            dv[0] = cos!(v[2]);
+            dv[1] = sin!(v[2]);
+            dv[2] = if v[0] > 0.0 {
                 2.0 * k - (OTHER_CONSTANT * GRAVITY * v[1]) / SIGMA - sin!(v[2]) / v[0]
             } else {
                 k - (OTHER_CONSTANT * GRAVITY * v[1]) / (2.0 * SIGMA)
-            }
-        });
-    InitialValueSystemProblem::new(0.0, vec![0.0, -1.0, 0.0], vec![dfr, dfz, dfangle])
+            };
+        },
+    )
 }
 
-fn evaluate(ks: f64, h: f64, s: f64) {
+fn evaluate(ks: f64, h: f64, s: f64) -> Result<(), SolverError> {
     let problem = create_problem(ks);
 
-    let approximation = explicit_euler_system_interval(&problem, h, s, 0);
+    let approximation = explicit_euler_system_interval(&problem, h, s, 0)?;
 
-    let rs: Vec<f64> = approximation.iter().map(|v| v[0].y).collect();
-    let zs: Vec<f64> = approximation.iter().map(|v| v[1].y).collect();
+    let rs: Vec<f64> = approximation.component(0);
+    let zs: Vec<f64> = approximation.component(1);
 
     let to_plot: Vec<_> = rs
         .iter()
@@ -79,4 +77,6 @@ fn evaluate(ks: f64, h: f64, s: f64) {
     let filename = IMAGE_DIR.to_owned().add(&format!("{}.png", ks));
     fg.save_to_png(&filename, 1200, 800)
         .expect("Unable to save file");
+
+    Ok(())
 }
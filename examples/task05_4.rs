@@ -1,7 +1,7 @@
 use gnuplot::Figure;
 use gnuplot::PlotOption::{Caption, Color};
 use ngdl_rust::definitions::{
-    ClosureSampleableFunction, InitialValueSystemProblem, ODEMethod, Point2D,
+    InitialValueSystemProblem, ODEMethod, OdeSystem, Point2D, SolverError,
 };
 use ngdl_rust::explicit_runge_kutta::{make_explicit_runge_kutta_with_tableau, Tableau};
 use ngdl_rust::plot_util::plot_line_on;
@@ -25,31 +25,29 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     ks.par_iter()
         .zip(curve_lengths.par_iter())
-        .for_each(|(k, s)| evaluate(*k, h, *s));
+        .try_for_each(|(k, s)| evaluate(*k, h, *s))?;
 
     Ok(())
 }
 
 /// v = vec![r, z, angle]
-fn create_problem(
-    ks: f64,
-) -> InitialValueSystemProblem<ClosureSampleableFunction<(f64, Vec<f64>), f64>> {
-    let dfr: ClosureSampleableFunction<(f64, Vec<f64>), f64> =
-        ClosureSampleableFunction::new(ks, |(_, v), _| cos!(v[2]));
-    let dfz: ClosureSampleableFunction<(f64, Vec<f64>), f64> =
-        ClosureSampleableFunction::new(ks, |(_, v), _| sin!(v[2]));
-    let dfangle: ClosureSampleableFunction<(f64, Vec<f64>), f64> =
-        ClosureSampleableFunction::new(ks, |(_, v), k| {
-            if v[0] > 0.0 {
+fn create_problem(k: f64) -> InitialValueSystemProblem<impl OdeSystem + Clone> {
+    InitialValueSystemProblem::new(
+        0.0,
+        vec![0.0, -1.0, 0.0],
+        move |_t: f64, v: &[f64], dv: &mut [f64]| {
+            dv[0] = cos!(v[2]);
+            dv[1] = sin!(v[2]);
+            dv[2] = if v[0] > 0.0 {
                 2.0 * k - (OTHER_CONSTANT * GRAVITY * v[1]) / SIGMA - sin!(v[2]) / v[0]
             } else {
                 k - (OTHER_CONSTANT * GRAVITY * v[1]) / (2.0 * SIGMA)
-            }
-        });
-    InitialValueSystemProblem::new(0.0, vec![0.0, -1.0, 0.0], vec![dfr, dfz, dfangle])
+            };
+        },
+    )
 }
 
-fn evaluate(ks: f64, h: f64, s: f64) {
+fn evaluate(ks: f64, h: f64, s: f64) -> Result<(), SolverError> {
     let problem = create_problem(ks);
     let tableau = Tableau::new(
         vec![0.0, 0.5, 1.0, 1.0],                                     // cs
@@ -59,10 +57,10 @@ fn evaluate(ks: f64, h: f64, s: f64) {
 
     let rk_method = make_explicit_runge_kutta_with_tableau(problem, h, tableau);
 
-    let approximation = rk_method.interval(s, 0);
+    let approximation = rk_method.interval(s, 0)?;
 
-    let rs: Vec<f64> = approximation.iter().map(|v| v[0].y).collect();
-    let zs: Vec<f64> = approximation.iter().map(|v| v[1].y).collect();
+    let rs: Vec<f64> = approximation.component(0);
+    let zs: Vec<f64> = approximation.component(1);
 
     let to_plot: Vec<_> = rs
         .iter()
@@ -87,4 +85,6 @@ fn evaluate(ks: f64, h: f64, s: f64) {
     let filename = IMAGE_DIR.to_owned().add(&format!("{}.png", ks));
     fg.save_to_png(&filename, 1200, 800)
         .expect("Unable to save file");
+
+    Ok(())
 }
@@ -2,7 +2,7 @@ use gnuplot::Coordinate::Graph;
 use gnuplot::Figure;
 use gnuplot::PlotOption::{Caption, Color, PointSymbol};
 use ngdl_rust::definitions::{
-    ClosureSampleableFunction, Function1D, InitialValueSystemProblem, Interval, ODEMethod, Point2D,
+    Function1D, InitialValueSystemProblem, Interval, ODEMethod, OdeSystem, Point2D,
 };
 use ngdl_rust::euler_explicit::make_explicit_euler_method_system;
 use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
@@ -28,18 +28,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     let exact_sampled = sample_function(exact, interval, 1000);
 
     let euler_method = make_explicit_euler_method_system(create_problem(PI / 2.0), h);
-    let euler_sampled: Vec<Point2D> = euler_method
-        .interval(t_target, 0)
-        .iter()
-        .map(|v| v[0])
-        .collect();
+    let euler_sampled: Vec<Point2D> = euler_method.interval(t_target, 0)?.component_points(0);
 
     let rk_method = make_classic_runge_kutta(create_problem(PI / 2.0), h);
-    let rk_sampled: Vec<Point2D> = rk_method
-        .interval(t_target, 0)
-        .iter()
-        .map(|v| v[0])
-        .collect();
+    let rk_sampled: Vec<Point2D> = rk_method.interval(t_target, 0)?.component_points(0);
 
     let mut fg = Figure::new();
     let axis = fg.axes2d().set_legend(Graph(0.3), Graph(1.0), &[], &[]);
@@ -75,18 +67,13 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn create_problem(
-    x_0: f64,
-) -> InitialValueSystemProblem<ClosureSampleableFunction<(f64, Vec<f64>), f64>> {
-    let dfx: ClosureSampleableFunction<(f64, Vec<f64>), f64> =
-        ClosureSampleableFunction::new(x_0, |(_t, v), _| {
-            // println!("t={}, z={}", t, v[1]);
-            v[1]
-        }); // x' = z
-    let dfz: ClosureSampleableFunction<(f64, Vec<f64>), f64> =
-        ClosureSampleableFunction::new(x_0, |(_t, v), x_start| {
-            // println!("t={}, x={}, x_start={}, sqrt={}", t, v[0], x_start, sqrt!(v[0] - x_start));
-            sqrt!(v[0] - x_start)
-        });
-    InitialValueSystemProblem::new(0.0, vec![x_0, 0.0], vec![dfx, dfz])
+fn create_problem(x_0: f64) -> InitialValueSystemProblem<impl OdeSystem> {
+    InitialValueSystemProblem::new(
+        0.0,
+        vec![x_0, 0.0],
+        move |_t: f64, v: &[f64], dv: &mut [f64]| {
+            dv[0] = v[1]; // x' = z
+            dv[1] = sqrt!(v[0] - x_0);
+        },
+    )
 }
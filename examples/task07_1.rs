@@ -1,6 +1,8 @@
 use gnuplot::Figure;
 use gnuplot::PlotOption::{Caption, Color};
-use ngdl_rust::definitions::{Function, InitialValueSystemProblem, Interval, ODEMethod, Point2D};
+use ngdl_rust::definitions::{
+    Function, FunctionSystem, InitialValueSystemProblem, Interval, ODEMethod, Point2D, SolverError,
+};
 use ngdl_rust::euler_explicit::make_explicit_euler_method_system;
 use ngdl_rust::plot_util::{plot_line_on, plot_line_points_on};
 use ngdl_rust::util::sample_function_generic;
@@ -35,19 +37,19 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let exact_points_2 = sample_function_generic(exact_2, Interval::new(0.0, 4.0), 10000);
 
-    evaluate("problem_1", h, 4.0, create_problem_1(), exact_points_1);
-    evaluate("problem_2", h, 15.0, create_problem_2(), exact_points_2);
+    evaluate("problem_1", h, 4.0, create_problem_1(), exact_points_1)?;
+    evaluate("problem_2", h, 15.0, create_problem_2(), exact_points_2)?;
 
     Ok(())
 }
 
-fn create_problem_1() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
+fn create_problem_1() -> InitialValueSystemProblem<FunctionSystem> {
     let dfx: Function<(f64, Vec<f64>)> = |(_t, v)| -v[0] + 5.0 * v[1];
     let dfy: Function<(f64, Vec<f64>)> = |(_t, v)| -5.0 * v[0] - v[1];
     InitialValueSystemProblem::new(0.0, vec![1.0, 2.0], vec![dfx, dfy])
 }
 
-fn create_problem_2() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
+fn create_problem_2() -> InitialValueSystemProblem<FunctionSystem> {
     let dfx: Function<(f64, Vec<f64>)> = |(_t, v)| v[1];
     let dfy: Function<(f64, Vec<f64>)> = |(_t, v)| -4.0 * v[0];
     InitialValueSystemProblem::new(0.0, vec![1.0, 2.0], vec![dfx, dfy])
@@ -57,14 +59,14 @@ fn evaluate(
     name: &str,
     h: f64,
     target: f64,
-    prob: InitialValueSystemProblem<Function<(f64, Vec<f64>)>>,
+    prob: InitialValueSystemProblem<FunctionSystem>,
     exact: Vec<Point2D>,
-) {
+) -> Result<(), SolverError> {
     let euler_method = make_explicit_euler_method_system(prob, h);
-    let approximation = euler_method.interval(target, 0);
+    let approximation = euler_method.interval(target, 0)?;
 
-    let xs: Vec<f64> = approximation.iter().map(|v| v[0].y).collect();
-    let ys: Vec<f64> = approximation.iter().map(|v| v[1].y).collect();
+    let xs: Vec<f64> = approximation.component(0);
+    let ys: Vec<f64> = approximation.component(1);
 
     let to_plot: Vec<_> = xs
         .iter()
@@ -87,4 +89,6 @@ fn evaluate(
     let filename = IMAGE_DIR.to_owned().add(name).add(".png");
     fg.save_to_png(&filename, 1200, 800)
         .expect("Unable to save file");
+
+    Ok(())
 }
@@ -1,6 +1,8 @@
 use gnuplot::Figure;
 use gnuplot::PlotOption::{Caption, Color};
-use ngdl_rust::definitions::{Function, InitialValueSystemProblem, Interval, ODEMethod, Point2D};
+use ngdl_rust::definitions::{
+    Function, FunctionSystem, InitialValueSystemProblem, Interval, ODEMethod, Point2D, SolverError,
+};
 use ngdl_rust::explicit_runge_kutta::{
     make_classic_runge_kutta, make_england_runge_kutta, make_three_eight_runge_kutta,
 };
@@ -38,19 +40,19 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let exact_points_2 = sample_function_generic(exact_2, Interval::new(0.0, 4.0), 150);
 
-    evaluate("problem_1", h1, 4.0, create_problem_1, exact_points_1);
-    evaluate("problem_2", h2, 15.0, create_problem_2, exact_points_2);
+    evaluate("problem_1", h1, 4.0, create_problem_1, exact_points_1)?;
+    evaluate("problem_2", h2, 15.0, create_problem_2, exact_points_2)?;
 
     Ok(())
 }
 
-fn create_problem_1() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
+fn create_problem_1() -> InitialValueSystemProblem<FunctionSystem> {
     let dfx: Function<(f64, Vec<f64>)> = |(_t, v)| -v[0] + 5.0 * v[1];
     let dfy: Function<(f64, Vec<f64>)> = |(_t, v)| -5.0 * v[0] - v[1];
     InitialValueSystemProblem::new(0.0, vec![1.0, 2.0], vec![dfx, dfy])
 }
 
-fn create_problem_2() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
+fn create_problem_2() -> InitialValueSystemProblem<FunctionSystem> {
     let dfx: Function<(f64, Vec<f64>)> = |(_t, v)| v[1];
     let dfy: Function<(f64, Vec<f64>)> = |(_t, v)| -4.0 * v[0];
     InitialValueSystemProblem::new(0.0, vec![1.0, 2.0], vec![dfx, dfy])
@@ -60,14 +62,14 @@ fn evaluate(
     name: &str,
     h: f64,
     target: f64,
-    prob_fn: fn() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>>,
+    prob_fn: impl Fn() -> InitialValueSystemProblem<FunctionSystem>,
     exact: Vec<Point2D>,
-) {
+) -> Result<(), SolverError> {
     let classic_rk_method = make_classic_runge_kutta(prob_fn(), h);
-    let approximation_classic = classic_rk_method.interval(target, 0);
+    let approximation_classic = classic_rk_method.interval(target, 0)?;
 
-    let xs_classic: Vec<f64> = approximation_classic.iter().map(|v| v[0].y).collect();
-    let ys_classic: Vec<f64> = approximation_classic.iter().map(|v| v[1].y).collect();
+    let xs_classic: Vec<f64> = approximation_classic.component(0);
+    let ys_classic: Vec<f64> = approximation_classic.component(1);
 
     let to_plot_classic: Vec<_> = xs_classic
         .iter()
@@ -87,10 +89,10 @@ fn evaluate(
     );
 
     let england_rk_method = make_england_runge_kutta(prob_fn(), h);
-    let approximation_england = england_rk_method.interval(target, 0);
+    let approximation_england = england_rk_method.interval(target, 0)?;
 
-    let xs_england: Vec<f64> = approximation_england.iter().map(|v| v[0].y).collect();
-    let ys_england: Vec<f64> = approximation_england.iter().map(|v| v[1].y).collect();
+    let xs_england: Vec<f64> = approximation_england.component(0);
+    let ys_england: Vec<f64> = approximation_england.component(1);
 
     let to_plot_england: Vec<_> = xs_england
         .iter()
@@ -110,10 +112,10 @@ fn evaluate(
     );
 
     let three_eight_rk_method = make_three_eight_runge_kutta(prob_fn(), h);
-    let approximation_three_eight = three_eight_rk_method.interval(target, 0);
+    let approximation_three_eight = three_eight_rk_method.interval(target, 0)?;
 
-    let xs_three_eight: Vec<f64> = approximation_three_eight.iter().map(|v| v[0].y).collect();
-    let ys_three_eight: Vec<f64> = approximation_three_eight.iter().map(|v| v[1].y).collect();
+    let xs_three_eight: Vec<f64> = approximation_three_eight.component(0);
+    let ys_three_eight: Vec<f64> = approximation_three_eight.component(1);
 
     let to_plot_three_eight: Vec<_> = xs_three_eight
         .iter()
@@ -159,4 +161,6 @@ fn evaluate(
     let filename = IMAGE_DIR.to_owned().add(name).add(".png");
     fg.save_to_png(&filename, 1200, 800)
         .expect("Unable to save file");
+
+    Ok(())
 }
@@ -1,10 +1,14 @@
 use gnuplot::Figure;
 use gnuplot::PlotOption::{Caption, Color};
 use ngdl_rust::adams_bashforth::make_adams_bashforth_2_method;
-use ngdl_rust::definitions::{Function, InitialValueSystemProblem, ODEMethod, Point2D};
+use ngdl_rust::definitions::{
+    InitialValueSystemProblem, ODEMethod, OdeSystem, Point2D, SolverError,
+};
 use ngdl_rust::euler_explicit::make_explicit_euler_method_system;
 use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
 use ngdl_rust::plot_util::{plot_line_on, plot_line_points_on};
+use ngdl_rust::sink::NpySink;
+use ngdl_rust::solution::Solution;
 use ngdl_rust::{powi, sqrt};
 use std::error::Error;
 use std::fs::create_dir_all;
@@ -22,24 +26,24 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let t_target = 3.0 * 59_400_000.0; // ~ Seconds in a year on Mars
 
-    test_euler(t_target);
-    test_runge_kutta(t_target);
-    test_adams_bashforth(t_target);
+    test_euler(t_target)?;
+    test_runge_kutta(t_target)?;
+    test_adams_bashforth(t_target)?;
 
     Ok(())
 }
 
-fn plot_data(data: Vec<Vec<Point2D>>, name: &str) {
-    let x_e: Vec<f64> = data.iter().map(|v| v[0].y).collect();
-    let y_e: Vec<f64> = data.iter().map(|v| v[1].y).collect();
+fn plot_data(data: Solution, name: &str) {
+    let x_e: Vec<f64> = data.component(0);
+    let y_e: Vec<f64> = data.component(1);
     let earth = Point2D::make_vec(x_e, y_e);
 
-    let x_m: Vec<f64> = data.iter().map(|v| v[2].y).collect();
-    let y_m: Vec<f64> = data.iter().map(|v| v[3].y).collect();
+    let x_m: Vec<f64> = data.component(2);
+    let y_m: Vec<f64> = data.component(3);
     let mars = Point2D::make_vec(x_m, y_m);
 
-    let x_s: Vec<f64> = data.iter().map(|v| v[4].y).collect();
-    let y_s: Vec<f64> = data.iter().map(|v| v[5].y).collect();
+    let x_s: Vec<f64> = data.component(4);
+    let y_s: Vec<f64> = data.component(5);
     let sun = Point2D::make_vec(x_s, y_s);
 
     let mut fg = Figure::new();
@@ -52,33 +56,43 @@ fn plot_data(data: Vec<Vec<Point2D>>, name: &str) {
         .expect("Unable to save file");
 }
 
-fn test_runge_kutta(t_target: f64) {
+fn test_runge_kutta(t_target: f64) -> Result<(), SolverError> {
     let h = 10000.0;
 
     let rk_method = make_classic_runge_kutta(create_problem(), h);
-    let data = rk_method.interval(t_target, 0);
+    let data = rk_method.interval(t_target, 0)?;
 
     plot_data(data, "rk");
+
+    Ok(())
 }
 
-fn test_euler(t_target: f64) {
+fn test_euler(t_target: f64) -> Result<(), SolverError> {
     let h = 100.0;
 
     let euler_method = make_explicit_euler_method_system(create_problem(), h);
-    let data = euler_method.interval(t_target, 0);
+    // Almost two million states, streamed to disk instead of kept in memory
+    let mut sink = NpySink::create(IMAGE_DIR.to_owned().add("euler.npy"))?;
+    euler_method.interval_into(t_target, 0, &mut sink)?;
+    // Every 100th state is plenty for the plot
+    let data = euler_method.interval(t_target, 100)?;
 
     plot_data(data, "euler");
+
+    Ok(())
 }
 
-fn test_adams_bashforth(t_target: f64) {
+fn test_adams_bashforth(t_target: f64) -> Result<(), SolverError> {
     let h = 10000.0;
 
     let ab_method = make_adams_bashforth_2_method(create_problem, h, |ivp, h| {
         make_explicit_euler_method_system(ivp, h)
     });
-    let data = ab_method.interval(t_target, 0);
+    let data = ab_method.interval(t_target, 0)?;
 
     plot_data(data, "adams_bashforth");
+
+    Ok(())
 }
 
 /// v = vec![x_E, y_E, x_M, y_M, x_S, y_S, same for dt]
@@ -94,57 +108,40 @@ fn test_adams_bashforth(t_target: f64) {
 /// y'_M = 9
 /// x'_S = 10
 /// y'_S = 11
-fn create_problem() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
-    let dfx_e: Function<(f64, Vec<f64>)> = |(_t, r)| r[6];
-    let dfy_e: Function<(f64, Vec<f64>)> = |(_t, r)| r[7];
-    let dfx_m: Function<(f64, Vec<f64>)> = |(_t, r)| r[8];
-    let dfy_m: Function<(f64, Vec<f64>)> = |(_t, r)| r[9];
-    let dfx_s: Function<(f64, Vec<f64>)> = |(_t, r)| r[10];
-    let dfy_s: Function<(f64, Vec<f64>)> = |(_t, r)| r[11];
-
-    let ddfx_e: Function<(f64, Vec<f64>)> = |(_t, r)| {
-        (-1.0 * GRAVITY * MASS_EARTH * MASS_MARS * (r[0] - r[2])
-            / powi!(sqrt!(powi!(r[0] - r[2], 2) + powi!(r[1] - r[3], 2)), 3)
-            + GRAVITY * MASS_SUN * MASS_EARTH * (r[4] - r[0])
-                / powi!(sqrt!(powi!(r[0] - r[4], 2) + powi!(r[1] - r[5], 2)), 3))
-            / MASS_EARTH
-    };
-    let ddfy_e: Function<(f64, Vec<f64>)> = |(_t, r)| {
-        (-1.0 * GRAVITY * MASS_EARTH * MASS_MARS * (r[1] - r[3])
-            / powi!(sqrt!(powi!(r[0] - r[2], 2) + powi!(r[1] - r[3], 2)), 3)
-            + GRAVITY * MASS_SUN * MASS_EARTH * (r[5] - r[1])
-                / powi!(sqrt!(powi!(r[0] - r[4], 2) + powi!(r[1] - r[5], 2)), 3))
-            / MASS_EARTH
-    };
-    let ddfx_m: Function<(f64, Vec<f64>)> = |(_t, r)| {
-        (GRAVITY * MASS_EARTH * MASS_MARS * (r[0] - r[2])
-            / powi!(sqrt!(powi!(r[0] - r[2], 2) + powi!(r[1] - r[3], 2)), 3)
-            + GRAVITY * MASS_SUN * MASS_MARS * (r[4] - r[2])
-                / powi!(sqrt!(powi!(r[4] - r[2], 2) + powi!(r[5] - r[3], 2)), 3))
-            / MASS_MARS
-    };
-    let ddfy_m: Function<(f64, Vec<f64>)> = |(_t, r)| {
-        (GRAVITY * MASS_EARTH * MASS_MARS * (r[1] - r[3])
-            / powi!(sqrt!(powi!(r[0] - r[2], 2) + powi!(r[1] - r[3], 2)), 3)
-            + GRAVITY * MASS_SUN * MASS_MARS * (r[5] - r[3])
-                / powi!(sqrt!(powi!(r[4] - r[2], 2) + powi!(r[5] - r[3], 2)), 3))
-            / MASS_MARS
-    };
-    let ddfx_s: Function<(f64, Vec<f64>)> = |(_t, r)| {
-        (-1.0 * GRAVITY * MASS_SUN * MASS_EARTH * (r[4] - r[0])
-            / powi!(sqrt!(powi!(r[4] - r[0], 2) + powi!(r[5] - r[1], 2)), 3)
-            + -1.0 * GRAVITY * MASS_SUN * MASS_MARS * (r[4] - r[2])
-                / powi!(sqrt!(powi!(r[4] - r[2], 2) + powi!(r[5] - r[3], 2)), 3))
-            / MASS_SUN
-    };
-    let ddfy_s: Function<(f64, Vec<f64>)> = |(_t, r)| {
-        (-1.0 * GRAVITY * MASS_SUN * MASS_EARTH * (r[5] - r[1])
-            / powi!(sqrt!(powi!(r[4] - r[0], 2) + powi!(r[5] - r[1], 2)), 3)
-            + -1.0 * GRAVITY * MASS_SUN * MASS_MARS * (r[5] - r[3])
-                / powi!(sqrt!(powi!(r[4] - r[2], 2) + powi!(r[5] - r[3], 2)), 3))
-            / MASS_SUN
-    };
+struct SolarSystem;
+
+impl OdeSystem for SolarSystem {
+    fn rhs(&self, _t: f64, r: &[f64], dr: &mut [f64]) {
+        // Positions change with the velocities
+        dr[..6].copy_from_slice(&r[6..]);
+
+        // Every distance is only computed once per evaluation
+        let dist_em_cubed = powi!(sqrt!(powi!(r[0] - r[2], 2) + powi!(r[1] - r[3], 2)), 3);
+        let dist_es_cubed = powi!(sqrt!(powi!(r[0] - r[4], 2) + powi!(r[1] - r[5], 2)), 3);
+        let dist_ms_cubed = powi!(sqrt!(powi!(r[4] - r[2], 2) + powi!(r[5] - r[3], 2)), 3);
+
+        dr[6] = (-GRAVITY * MASS_EARTH * MASS_MARS * (r[0] - r[2]) / dist_em_cubed
+            + GRAVITY * MASS_SUN * MASS_EARTH * (r[4] - r[0]) / dist_es_cubed)
+            / MASS_EARTH;
+        dr[7] = (-GRAVITY * MASS_EARTH * MASS_MARS * (r[1] - r[3]) / dist_em_cubed
+            + GRAVITY * MASS_SUN * MASS_EARTH * (r[5] - r[1]) / dist_es_cubed)
+            / MASS_EARTH;
+        dr[8] = (GRAVITY * MASS_EARTH * MASS_MARS * (r[0] - r[2]) / dist_em_cubed
+            + GRAVITY * MASS_SUN * MASS_MARS * (r[4] - r[2]) / dist_ms_cubed)
+            / MASS_MARS;
+        dr[9] = (GRAVITY * MASS_EARTH * MASS_MARS * (r[1] - r[3]) / dist_em_cubed
+            + GRAVITY * MASS_SUN * MASS_MARS * (r[5] - r[3]) / dist_ms_cubed)
+            / MASS_MARS;
+        dr[10] = (-GRAVITY * MASS_SUN * MASS_EARTH * (r[4] - r[0]) / dist_es_cubed
+            - GRAVITY * MASS_SUN * MASS_MARS * (r[4] - r[2]) / dist_ms_cubed)
+            / MASS_SUN;
+        dr[11] = (-GRAVITY * MASS_SUN * MASS_EARTH * (r[5] - r[1]) / dist_es_cubed
+            - GRAVITY * MASS_SUN * MASS_MARS * (r[5] - r[3]) / dist_ms_cubed)
+            / MASS_SUN;
+    }
+}
 
+fn create_problem() -> InitialValueSystemProblem<SolarSystem> {
     InitialValueSystemProblem::new(
         0.0,
         vec![
@@ -161,9 +158,6 @@ fn create_problem() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
             0.0,
             0.0,
         ],
-        vec![
-            dfx_e, dfy_e, dfx_m, dfy_m, dfx_s, dfy_s, ddfx_e, ddfy_e, ddfx_m, ddfy_m, ddfx_s,
-            ddfy_s,
-        ],
+        SolarSystem,
     )
 }
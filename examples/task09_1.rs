@@ -3,12 +3,15 @@ use gnuplot::PlotOption::{Caption, Color};
 use gnuplot::TickOption::Format;
 use gnuplot::{AxesCommon, Figure};
 use ngdl_rust::adams_bashforth::{make_adams_bashforth_2_method, make_adams_bashforth_3_method};
-use ngdl_rust::definitions::{Function, InitialValueSystemProblem, Point2D, SampleableFunction};
+use ngdl_rust::definitions::{
+    Function, FunctionSystem, InitialValueSystemProblem, Point2D, SampleableFunction,
+};
 use ngdl_rust::euler_explicit::make_explicit_euler_method_system;
 use ngdl_rust::explicit_runge_kutta::{
     make_2nd_order_runge_kutta, make_classic_runge_kutta, make_heun_method,
 };
 use ngdl_rust::hack::{make_adams_moulton_hack_method, make_milne_simpson_hack_method};
+use ngdl_rust::method_info::MethodInfo;
 use ngdl_rust::milne_simpson::make_milne_simpson_method;
 use ngdl_rust::nystroem::make_nystroem_3_method;
 use ngdl_rust::plot_util::plot_line_points_on;
@@ -25,6 +28,11 @@ const T_TARGET: f64 = 1.0;
 
 macro_rules! test_method {
     ($hs: expr, $exact_value: expr, $name: expr, $method: expr) => {
+        let info = $method(create_problem, 0.1, |ivp, h| {
+            make_explicit_euler_method_system(ivp, h)
+        });
+        println!("{}: nominal order {}", info.name(), info.order());
+
         let err_euler: Vec<f64> = $hs
             .iter()
             .map(|h| {
@@ -216,7 +224,7 @@ fn plot_convergence(
         .expect("Unable to save file");
 }
 
-fn create_problem() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
+fn create_problem() -> InitialValueSystemProblem<FunctionSystem> {
     let df: Function<(f64, Vec<f64>)> = |(t, v)| -t * t * v[0];
 
     InitialValueSystemProblem::new(0.0, vec![E], vec![df])
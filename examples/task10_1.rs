@@ -1,5 +1,8 @@
-use ngdl_rust::definitions::{Function, InitialValueSystemProblem, SampleableFunction};
+use ngdl_rust::definitions::{
+    Function, FunctionSystem, InitialValueSystemProblem, SampleableFunction,
+};
 use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+use ngdl_rust::solve::{solve, Method, SolverOptions};
 use ngdl_rust::{abs, powi};
 use std::error::Error;
 
@@ -21,10 +24,30 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("\tExact z(t_end) = {}", EXACT_Z);
 
     test_runge_kutta();
+    test_dopri5()?;
 
     Ok(())
 }
 
+fn test_dopri5() -> Result<(), Box<dyn Error>> {
+    // y is ten orders of magnitude smaller than x and z, so it needs its own absolute tolerance
+    let options = SolverOptions::until(T_TARGET)
+        .atol(vec![1e-8, 1e-14, 1e-8])
+        .rtol(1e-6);
+    let (solution, _) = solve(create_problem(), &Method::dopri5(), options)?;
+    let data = solution.final_state().unwrap();
+
+    println!("\nDOPRI5 ({}):", solution.stats());
+    println!("\tx'(t_end) = {}", data[0]);
+    println!("\ty'(t_end) = {}", data[1]);
+    println!("\tz'(t_end) = {}", data[2]);
+
+    println!("\n\terror_x = {:e}", abs!(data[0] - EXACT_X));
+    println!("\terror_y = {:e}", abs!(data[1] - EXACT_Y));
+    println!("\terror_z = {:e}", abs!(data[2] - EXACT_Z));
+    Ok(())
+}
+
 fn test_runge_kutta() {
     let rk_method = make_classic_runge_kutta(create_problem(), H);
     let data = rk_method.value_at(T_TARGET);
@@ -39,7 +62,7 @@ fn test_runge_kutta() {
     println!("\terror_z = {:e}", abs!(data[2] - EXACT_Z));
 }
 
-fn create_problem() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
+fn create_problem() -> InitialValueSystemProblem<FunctionSystem> {
     let dfx: Function<(f64, Vec<f64>)> = |(_t, r)| -0.04 * r[0] + powi!(10.0f64, 4) * r[1] * r[2];
     let dfy: Function<(f64, Vec<f64>)> = |(_t, r)| {
         0.04 * r[0] - powi!(10.0f64, 4) * r[1] * r[2] - 3.0 * powi!(10.0f64, 7) * r[1] * r[1]
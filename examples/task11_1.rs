@@ -10,18 +10,12 @@ use ngdl_rust::{ONE, TWO};
 use num::complex::Complex64;
 use std::error::Error;
 use std::fs::create_dir_all;
-use std::ops::Add;
+use std::ops::{Add, ControlFlow};
 
 const NUM_SAMPLES: usize = 1500;
 const IMAGE_DIR: &str = "./img_task11_1/";
-const GAMMA: Complex64 = Complex64::new(
-    0.788675134594812882254574390250978727823800875635063438009,
-    0.0,
-);
-const GAMMA_NEG: Complex64 = Complex64::new(
-    0.211324865405187117745425609749021272176199124364936561990,
-    0.0,
-);
+const GAMMA: Complex64 = Complex64::new(0.788_675_134_594_812_9, 0.0);
+const GAMMA_NEG: Complex64 = Complex64::new(0.211_324_865_405_187_1, 0.0);
 
 const RE_INTERVAL: Interval = Interval::new(-4.0, 3.0);
 const IM_INTERVAL: Interval = Interval::new(-3.5, 3.5);
@@ -43,7 +37,14 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn plot_stability_region(name: &str, caption: &str, stability_region: &Vec<Point2D>) {
+fn report_progress(done: usize, total: usize) -> ControlFlow<()> {
+    if done == 0 {
+        println!("Sampling stability area. Projected samples: {}", total);
+    }
+    ControlFlow::Continue(())
+}
+
+fn plot_stability_region(name: &str, caption: &str, stability_region: &[Point2D]) {
     let mut fg = Figure::new();
     let axis = fg
         .axes2d()
@@ -55,7 +56,7 @@ fn plot_stability_region(name: &str, caption: &str, stability_region: &Vec<Point
 
     plot_points_on(
         axis,
-        &stability_region,
+        stability_region,
         &[Caption(caption), Color("green"), PointSymbol('o')],
     );
 
@@ -73,7 +74,8 @@ fn plot_stability_area_rk(ss: usize) {
                 .norm()
         });
 
-    let stability_region = sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL);
+    let stability_region =
+        sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL, report_progress);
 
     plot_stability_region(
         &ss.to_string(),
@@ -91,7 +93,8 @@ fn plot_stability_area_rk_b_1() {
             .norm()
     };
 
-    let stability_region = sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL);
+    let stability_region =
+        sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL, report_progress);
 
     plot_stability_region("b_1", "Task 11, 1, b) 1.", &stability_region);
 }
@@ -102,7 +105,8 @@ fn plot_stability_area_rk_sdirk() {
             .norm()
     };
 
-    let stability_region = sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL);
+    let stability_region =
+        sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL, report_progress);
 
     plot_stability_region("sdirk", "SDIRK 3rd order", &stability_region);
 }
@@ -114,7 +118,8 @@ fn plot_stability_area_rk_sdirk_neg() {
         .norm()
     };
 
-    let stability_region = sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL);
+    let stability_region =
+        sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL, report_progress);
 
     plot_stability_region(
         "sdirk_2",
@@ -126,7 +131,8 @@ fn plot_stability_area_rk_sdirk_neg() {
 fn plot_stability_area_implicit_euler() {
     let r: Function<Complex64> = |z| (ONE / (ONE - z)).norm();
 
-    let stability_region = sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL);
+    let stability_region =
+        sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL, report_progress);
 
     plot_stability_region("implicit_euler", "Implicit euler", &stability_region);
 }
@@ -134,7 +140,8 @@ fn plot_stability_area_implicit_euler() {
 fn plot_stability_area_implicit_midpoint() {
     let r: Function<Complex64> = |z| ((-z - TWO) / (z - TWO)).norm();
 
-    let stability_region = sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL);
+    let stability_region =
+        sample_stability_area(r, NUM_SAMPLES, RE_INTERVAL, IM_INTERVAL, report_progress);
 
     plot_stability_region("implicit_midpoint", "Implicit midpoint", &stability_region);
 }
@@ -19,18 +19,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     create_dir_all(IMAGE_DIR)?;
 
     // AB
-    let (pts, p0, p1) = make_adams_wok(&vec![1.0, -1.0], &vec![0.0, 1.0]);
+    let (pts, p0, p1) = make_adams_wok(&[1.0, -1.0], &[0.0, 1.0]);
     plot_stability_region("adam_bashforth_1", "Adam-Bashforth k=1", &pts, p0, p1);
 
-    let (pts, p0, p1) = make_adams_wok(
-        &vec![1.0, -1.0, 0.0],
-        &vec![23.0 / 12.0, -16.0 / 12.0, 5.0 / 12.0],
-    );
+    let (pts, p0, p1) = make_adams_wok(&[1.0, -1.0, 0.0], &[23.0 / 12.0, -16.0 / 12.0, 5.0 / 12.0]);
     plot_stability_region("adam_bashforth_3", "Adam-Bashforth k=3", &pts, p0, p1);
 
     let (pts, p0, p1) = make_adams_wok(
-        &vec![1.0, -1.0, 0.0, 0.0, 0.0],
-        &vec![
+        &[1.0, -1.0, 0.0, 0.0, 0.0],
+        &[
             1901.0 / 720.0,
             -2774.0 / 720.0,
             2616.0 / 720.0,
@@ -42,8 +39,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // From http://www.mymathlib.com/c_source/diffeq/adams/adams_7_steps.c
     let (pts, p0, p1) = make_adams_wok(
-        &vec![1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
-        &vec![
+        &[1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        &[
             198721.0 / 60480.0,
             -447288.0 / 60480.0,
             705549.0 / 60480.0,
@@ -56,18 +53,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     plot_stability_region("adam_bashforth_7", "Adam-Bashforth k=7", &pts, p0, p1);
 
     // AM
-    let (pts, p0, p1) = make_adams_wok(&vec![1.0, -1.0], &vec![1.0, 0.0]);
+    let (pts, p0, p1) = make_adams_wok(&[1.0, -1.0], &[1.0, 0.0]);
     plot_stability_region("adam_moulton_1", "Adam-Moulton k=1", &pts, p0, p1);
 
-    let (pts, p0, p1) = make_adams_wok(
-        &vec![1.0, -1.0, 0.0],
-        &vec![5.0 / 12.0, 2.0 / 3.0, 1.0 / 12.0],
-    );
+    let (pts, p0, p1) = make_adams_wok(&[1.0, -1.0, 0.0], &[5.0 / 12.0, 2.0 / 3.0, 1.0 / 12.0]);
     plot_stability_region("adam_moulton_3", "Adam-Moulton k=3", &pts, p0, p1);
 
     let (pts, p0, p1) = make_adams_wok(
-        &vec![1.0, -1.0, 0.0, 0.0, 0.0],
-        &vec![
+        &[1.0, -1.0, 0.0, 0.0, 0.0],
+        &[
             251.0 / 720.0,
             646.0 / 720.0,
             -264.0 / 720.0,
@@ -78,8 +72,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     plot_stability_region("adam_moulton_5", "Adam-Moulton k=5", &pts, p0, p1);
 
     let (pts, p0, p1) = make_adams_wok(
-        &vec![1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
-        &vec![
+        &[1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        &[
             19087.0 / 60480.0,
             65112.0 / 60480.0,
             -46461.0 / 60480.0,
@@ -1,7 +1,8 @@
 use gnuplot::PlotOption::{Caption, Color};
 use gnuplot::{AxesCommon, Figure};
-use ngdl_rust::definitions::{Function, InitialValueSystemProblem, Point2D};
+use ngdl_rust::definitions::{InitialValueSystemProblem, OdeSystem, Point2D};
 use ngdl_rust::embedded_rk::{make_dopri5, make_embedded_rk_1st_order};
+use ngdl_rust::observer::ProgressReporter;
 use ngdl_rust::plot_util::plot_line_points_on;
 use std::error::Error;
 use std::fs::create_dir_all;
@@ -16,14 +17,19 @@ const T_TARGET: f64 = 1.2;
 fn main() -> Result<(), Box<dyn Error>> {
     create_dir_all(IMAGE_DIR)?;
 
-    let mut embedded_rk = make_embedded_rk_1st_order(create_problem, H_START, TOLERANCE);
-    let approximation = embedded_rk.interval(T_TARGET, 0);
+    let mut embedded_rk =
+        make_embedded_rk_1st_order(|| create_problem(EPSILON), H_START, TOLERANCE);
+    let approximation =
+        embedded_rk.interval_observed(T_TARGET, 0, &mut ProgressReporter::every(500))?;
 
-    let mut dopri = make_dopri5(create_problem, H_START, TOLERANCE);
-    let approximation_dop = dopri.interval(T_TARGET, 0);
+    let mut dopri = make_dopri5(|| create_problem(EPSILON), H_START, TOLERANCE);
+    let approximation_dop = dopri.interval(T_TARGET, 0)?;
 
-    let xs: Vec<f64> = approximation.iter().map(|v| v[0].x).collect();
-    let ys: Vec<f64> = approximation.iter().map(|v| v[0].y).collect();
+    println!("Heun/Euler: {}", approximation.stats());
+    println!("DOPRI5: {}", approximation_dop.stats());
+
+    let xs: Vec<f64> = approximation.times().to_vec();
+    let ys: Vec<f64> = approximation.component(0);
 
     let to_plot: Vec<_> = xs
         .iter()
@@ -31,8 +37,8 @@ fn main() -> Result<(), Box<dyn Error>> {
         .map(|(x, y)| Point2D::new(*x, y))
         .collect();
 
-    let xs_dop: Vec<f64> = approximation_dop.iter().map(|v| v[0].x).collect();
-    let ys_dop: Vec<f64> = approximation_dop.iter().map(|v| v[0].y).collect();
+    let xs_dop: Vec<f64> = approximation_dop.times().to_vec();
+    let ys_dop: Vec<f64> = approximation_dop.component(0);
 
     let to_plot_dop: Vec<_> = xs_dop
         .iter()
@@ -51,11 +57,9 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // hs
     let hs: Vec<f64> = approximation
-        .iter()
-        .skip(1)
-        .map(|v| v[0].x)
-        .zip(approximation.iter().map(|v| v[0].x))
-        .map(|(t2, t1)| t2 - t1)
+        .times()
+        .windows(2)
+        .map(|ts| ts[1] - ts[0])
         .collect();
 
     let hs_to_plot: Vec<_> = xs
@@ -65,11 +69,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         .collect();
 
     let hs_dop: Vec<f64> = approximation_dop
-        .iter()
-        .skip(1)
-        .map(|v| v[0].x)
-        .zip(approximation_dop.iter().map(|v| v[0].x))
-        .map(|(t2, t1)| t2 - t1)
+        .times()
+        .windows(2)
+        .map(|ts| ts[1] - ts[0])
         .collect();
 
     let hs_to_plot_dop: Vec<_> = xs_dop
@@ -90,8 +92,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn create_problem() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
-    let dfx: Function<(f64, Vec<f64>)> = |(_t, v)| v[1];
-    let dfy: Function<(f64, Vec<f64>)> = |(_t, v)| -EPSILON * (v[0] * v[0] - 1.0) * v[1] - v[0];
-    InitialValueSystemProblem::new(0.0, vec![1.0, 1.0], vec![dfx, dfy])
+/// Van der Pol oscillator, epsilon can be chosen at runtime.
+struct VanDerPol {
+    epsilon: f64,
+}
+
+impl OdeSystem for VanDerPol {
+    fn rhs(&self, _t: f64, v: &[f64], dv: &mut [f64]) {
+        dv[0] = v[1];
+        dv[1] = -self.epsilon * (v[0] * v[0] - 1.0) * v[1] - v[0];
+    }
+}
+
+fn create_problem(epsilon: f64) -> InitialValueSystemProblem<VanDerPol> {
+    InitialValueSystemProblem::new(0.0, vec![1.0, 1.0], VanDerPol { epsilon })
 }
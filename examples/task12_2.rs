@@ -1,8 +1,11 @@
 use gnuplot::PlotOption::{Caption, Color};
 use gnuplot::{AxesCommon, Figure};
-use ngdl_rust::definitions::{Function, InitialValueSystemProblem, Point2D};
-use ngdl_rust::embedded_rk::{make_dopri5, make_embedded_rk_1st_order};
+use ngdl_rust::definitions::{Function, FunctionSystem, InitialValueSystemProblem, Point2D};
 use ngdl_rust::plot_util::plot_line_points_on;
+use ngdl_rust::solve::{solve, Method, SolverOptions};
+use ngdl_rust::step_size_controller::{
+    GustafssonController, PiController, PidController, StepSizeController,
+};
 use ngdl_rust::{powf, powi};
 use std::error::Error;
 use std::fs::create_dir_all;
@@ -10,18 +13,22 @@ use std::ops::Add;
 
 const IMAGE_DIR: &str = "./img_task12_2/";
 const MU: f64 = 0.012277471;
-const H_START: f64 = 0.1;
 const TOLERANCE: f64 = 0.001;
 const T_TARGET: f64 = 17.06522;
 
 fn main() -> Result<(), Box<dyn Error>> {
     create_dir_all(IMAGE_DIR)?;
 
-    let mut dopri = make_dopri5(create_problem, H_START, TOLERANCE);
-    let approximation_dop = dopri.interval(T_TARGET, 0);
+    let options = SolverOptions::until(T_TARGET).atol(TOLERANCE).rtol(0.0);
+    let (approximation_dop, _) = solve(create_problem(), &Method::dopri5(), options)?;
+    println!("I: {}", approximation_dop.stats());
 
-    let xs_dop: Vec<f64> = approximation_dop.iter().map(|v| v[0].y).collect();
-    let ys_dop: Vec<f64> = approximation_dop.iter().map(|v| v[2].y).collect();
+    compare_controller("PI", PiController::default())?;
+    compare_controller("PID", PidController::default())?;
+    compare_controller("Gustafsson", GustafssonController::default())?;
+
+    let xs_dop: Vec<f64> = approximation_dop.component(0);
+    let ys_dop: Vec<f64> = approximation_dop.component(2);
 
     let to_plot_dop: Vec<_> = xs_dop
         .iter()
@@ -39,11 +46,9 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // hs
     let hs_dop: Vec<f64> = approximation_dop
-        .iter()
-        .skip(1)
-        .map(|v| v[0].x)
-        .zip(approximation_dop.iter().map(|v| v[0].x))
-        .map(|(t2, t1)| t2 - t1)
+        .times()
+        .windows(2)
+        .map(|ts| ts[1] - ts[0])
         .collect();
 
     let hs_to_plot_dop: Vec<_> = hs_dop
@@ -63,7 +68,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn create_problem() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
+fn compare_controller<C: StepSizeController + 'static>(
+    name: &str,
+    controller: C,
+) -> Result<(), Box<dyn Error>> {
+    let options = SolverOptions::until(T_TARGET)
+        .atol(TOLERANCE)
+        .rtol(0.0)
+        .controller(controller);
+    let (approximation, _) = solve(create_problem(), &Method::dopri5(), options)?;
+    println!("{}: {}", name, approximation.stats());
+    Ok(())
+}
+
+fn create_problem() -> InitialValueSystemProblem<FunctionSystem> {
     let dfx1: Function<(f64, Vec<f64>)> = |(_t, v)| v[1];
     let dfy1: Function<(f64, Vec<f64>)> = |(_t, v)| {
         v[0] + 2.0 * v[3]
@@ -73,7 +91,7 @@ fn create_problem() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>> {
 
     let dfx2: Function<(f64, Vec<f64>)> = |(_t, v)| v[3];
     let dfy2: Function<(f64, Vec<f64>)> = |(_t, v)| {
-        v[2] - 2.0 * v[2]
+        v[2] - 2.0 * v[1]
             - (1.0 - MU) * v[2] / powf!(powi!(v[0] + MU, 2) + powi!(v[2], 2), 1.5)
             - MU * v[2] / powf!(powi!(v[0] - 1.0 + MU, 2) + powi!(v[2], 2), 1.5)
     };
@@ -1,75 +1,158 @@
-use crate::definitions::{InitialValueSystemProblem, PointwiseAdd, SampleableFunction, ScalarMul};
+use crate::definitions::{InitialValueSystemProblem, OdeSystem, Scalar, StateVector};
 use crate::generalized_explicit_k_step_method::{KStepMethod, KStepMethodStep};
 use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
+use crate::method_info::MethodInfo;
+use crate::stats::SolverStats;
 
 /// For now only a simple one like 2
 /// Formula for this is y_{n+2} = y_{n+1} + h * (3/2 * f_{n+1} - 1/2 * f_n)
 /// Linear method with a_2 = 1, a_1 = -1, a_0 = 0; b_2 = 0, b_1 = 3/2, b_0 = -1/2
-/// Important: Does not hit t_target exactly, because we need equidistant supports
+/// The grid is equidistant, so the value at a t_target between grid points is interpolated
 pub struct AdamsBashford2;
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> KStepMethodStep<FT> for AdamsBashford2 {
-    fn step(&self, _k: usize, dfs: &[FT], t: f64, last_values: &[Vec<f64>], h: f64) -> Vec<f64> {
-        let last_values_owned: Vec<f64> = last_values[1].clone();
-        let before_last_values_owned: Vec<f64> = last_values[0].clone();
-        dfs.iter()
-            .map(|df| {
-                1.5 * df.value_at((t, last_values_owned.clone()))
-                    - 0.5 * df.value_at((t - h, before_last_values_owned.clone()))
-            })
-            .collect::<Vec<f64>>()
-            .scalar_mul(h)
-            .pointwise_add(last_values_owned) // x_{n-1}
+impl<SYS: OdeSystem<S::Scalar>, S: StateVector> KStepMethodStep<SYS, S> for AdamsBashford2 {
+    fn step(
+        &self,
+        _system: &SYS,
+        _t: f64,
+        last_values: &[S],
+        last_slopes: &[S],
+        h: f64,
+        _stats: &mut SolverStats,
+    ) -> S {
+        let f_last = &last_slopes[1];
+        let f_before_last = &last_slopes[0];
+
+        let h = S::Scalar::from_f64(h);
+
+        let mut next_values = last_values[1].clone(); // x_{n-1}
+        next_values.axpy(h * S::Scalar::from_ratio(3.0, 2.0), f_last);
+        next_values.axpy(h * S::Scalar::from_ratio(-1.0, 2.0), f_before_last);
+        next_values
+    }
+}
+
+impl MethodInfo for AdamsBashford2 {
+    fn name(&self) -> &str {
+        "Adams-Bashforth 2"
+    }
+
+    fn order(&self) -> usize {
+        2
+    }
+
+    fn stages(&self) -> usize {
+        1
+    }
+
+    fn steps(&self) -> usize {
+        2
     }
 }
 
 /// Makes a system of ODEs into a sampleable function using an Adams-Bashforth method.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::adams_bashforth::make_adams_bashforth_2_method;
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, ODEMethod};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// let create_problem = || {
+///     let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+///     InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+/// };
+///
+/// // 1.05 is not on the grid of h = 0.1, the last value is interpolated there
+/// let solution = make_adams_bashforth_2_method(create_problem, 0.1, make_classic_runge_kutta)
+///     .interval(1.05, 1)
+///     .unwrap();
+///
+/// assert_eq!(solution.final_time(), Some(1.05));
+/// assert!((solution.final_state().unwrap()[0] - (-1.05f64).exp()).abs() < 1e-2);
+/// ```
 pub fn make_adams_bashforth_2_method<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
-    StartStep: OneStepMethodStep<FT>,
+    SYS: OdeSystem<S::Scalar>,
+    S: StateVector,
+    StartStep: OneStepMethodStep<SYS, S>,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS, S> + 'static,
+    StartGen: Fn(InitialValueSystemProblem<SYS, S>, f64) -> OneStepMethod<SYS, StartStep, S> + 'static,
 >(
-    ivp: fn() -> InitialValueSystemProblem<FT>,
+    ivp: IvpGen,
     h: f64,
-    start_method_gen: fn(
-        ivp: InitialValueSystemProblem<FT>,
-        h: f64,
-    ) -> OneStepMethod<FT, StartStep>,
-) -> KStepMethod<FT, AdamsBashford2, StartStep> {
-    KStepMethod::new(ivp, h, 2, AdamsBashford2, start_method_gen)
+    start_method_gen: StartGen,
+) -> KStepMethod<SYS, AdamsBashford2, StartStep, S> {
+    KStepMethod::new(
+        Box::new(ivp),
+        h,
+        2,
+        AdamsBashford2,
+        Box::new(start_method_gen),
+    )
 }
 
 /// 3rd order Adams Bashford method.
 pub struct AdamsBashford3;
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> KStepMethodStep<FT> for AdamsBashford3 {
-    fn step(&self, _k: usize, dfs: &[FT], t: f64, last_values: &[Vec<f64>], h: f64) -> Vec<f64> {
-        let last_values_owned: Vec<f64> = last_values[2].clone();
-        let before_last_values_owned: Vec<f64> = last_values[1].clone();
-        let before_before_last_values_owned: Vec<f64> = last_values[0].clone();
-        dfs.iter()
-            .map(|df| {
-                (23.0 / 12.0) * df.value_at((t, last_values_owned.clone()))
-                    - (16.0 / 12.0) * df.value_at((t - h, before_last_values_owned.clone()))
-                    + (5.0 / 12.0)
-                        * df.value_at((t - 2.0 * h, before_before_last_values_owned.clone()))
-            })
-            .collect::<Vec<f64>>()
-            .scalar_mul(h)
-            .pointwise_add(last_values_owned) // x_{n-2}
+impl<SYS: OdeSystem<S::Scalar>, S: StateVector> KStepMethodStep<SYS, S> for AdamsBashford3 {
+    fn step(
+        &self,
+        _system: &SYS,
+        _t: f64,
+        last_values: &[S],
+        last_slopes: &[S],
+        h: f64,
+        _stats: &mut SolverStats,
+    ) -> S {
+        let f_last = &last_slopes[2];
+        let f_before_last = &last_slopes[1];
+        let f_before_before_last = &last_slopes[0];
+
+        let h = S::Scalar::from_f64(h);
+
+        let mut next_values = last_values[2].clone(); // x_{n-2}
+        next_values.axpy(h * S::Scalar::from_ratio(23.0, 12.0), f_last);
+        next_values.axpy(h * S::Scalar::from_ratio(-16.0, 12.0), f_before_last);
+        next_values.axpy(h * S::Scalar::from_ratio(5.0, 12.0), f_before_before_last);
+        next_values
+    }
+}
+
+impl MethodInfo for AdamsBashford3 {
+    fn name(&self) -> &str {
+        "Adams-Bashforth 3"
+    }
+
+    fn order(&self) -> usize {
+        3
+    }
+
+    fn stages(&self) -> usize {
+        1
+    }
+
+    fn steps(&self) -> usize {
+        3
     }
 }
 
 /// Makes a system of ODEs into a sampleable function using an Adams-Bashforth method.
 pub fn make_adams_bashforth_3_method<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
-    StartStep: OneStepMethodStep<FT>,
+    SYS: OdeSystem<S::Scalar>,
+    S: StateVector,
+    StartStep: OneStepMethodStep<SYS, S>,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS, S> + 'static,
+    StartGen: Fn(InitialValueSystemProblem<SYS, S>, f64) -> OneStepMethod<SYS, StartStep, S> + 'static,
 >(
-    ivp: fn() -> InitialValueSystemProblem<FT>,
+    ivp: IvpGen,
     h: f64,
-    start_method_gen: fn(
-        ivp: InitialValueSystemProblem<FT>,
-        h: f64,
-    ) -> OneStepMethod<FT, StartStep>,
-) -> KStepMethod<FT, AdamsBashford3, StartStep> {
-    KStepMethod::new(ivp, h, 3, AdamsBashford3, start_method_gen)
+    start_method_gen: StartGen,
+) -> KStepMethod<SYS, AdamsBashford3, StartStep, S> {
+    KStepMethod::new(
+        Box::new(ivp),
+        h,
+        3,
+        AdamsBashford3,
+        Box::new(start_method_gen),
+    )
 }
@@ -1,16 +1,39 @@
-use crate::abs;
+use crate::solution::{OutputTimes, Solution};
+use crate::{abs, powi, sqrt};
 use derive_new::*;
-use std::fmt::{Display, Error, Formatter};
+use nalgebra::allocator::Allocator;
+use nalgebra::{DefaultAllocator, Dim, Matrix, VectorN};
+use num::traits::NumAssign;
+use num::{Complex, Zero};
+use std::fmt::{Debug, Display, Error, Formatter};
 use std::marker::PhantomData;
-use std::ops::Mul;
+use std::ops::{Mul, Neg};
 
 /// Generic type for a function -> R
 /// T can be e.g. f64, or (f64, f64).
 pub type Function<T> = fn(T) -> f64;
 /// Generic type for a function -> R that also takes additional data.
-/// This is necessary because constructing dynamic closures plays havoc with the Rust borrow checker...
+/// Mostly a leftover from before capturing closures were supported, but still handy for `Copy` data.
 pub type Closure<T, Data> = fn(T, Data) -> f64;
 
+/// Owned, possibly capturing closure T -> R.
+/// Use this when a problem depends on parameters that are only known at runtime.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{BoxedFunction, InitialValueSystemProblem, ODEMethod};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// for lambda in &[-1.0, -2.0, -4.0] {
+///     let lambda = *lambda;
+///     let df: BoxedFunction<(f64, Vec<f64>)> = Box::new(move |(_, v)| lambda * v[0]);
+///     let problem = InitialValueSystemProblem::new(0.0, vec![1.0], vec![df]);
+///     let solution = make_classic_runge_kutta(problem, 0.1).interval(1.0, 0).unwrap();
+///     assert!((solution.final_state().unwrap()[0] - lambda.exp()).abs() < 1e-4);
+/// }
+/// ```
+pub type BoxedFunction<T> = Box<dyn Fn(T) -> f64 + Send + Sync>;
+
 /// Type alias for a R -> R function.
 pub type Function1D = Function<f64>;
 /// Type alias
@@ -25,12 +48,15 @@ pub type Closure2D<T> = Closure<(f64, f64), T>;
 /// Type alias for a function of t x R^n -> R
 pub type FunctionND<T, R> = fn(T) -> R;
 
+/// Type alias for a system in the per-component form, using one plain function per component.
+pub type FunctionSystem = Vec<Function<(f64, Vec<f64>)>>;
+
 /// Type alias
 pub type SimpleDifferentiableFunction2D = SimpleDifferentiableFunction<(f64, f64)>;
 /// Type alias
 pub type SimpleDifferentiableFunction1D = SimpleDifferentiableFunction<f64>;
 
-impl<T, R> SampleableFunction<T, R> for FunctionND<T, R> {
+impl<T, R, F: Fn(T) -> R> SampleableFunction<T, R> for F {
     fn value_at(&self, input: T) -> R {
         self(input)
     }
@@ -73,22 +99,26 @@ impl ScalarMul<f64> for Vec<f64> {
     }
 }
 
-/// Implementation of DifferentiableFunction that uses Function<T>
+/// Implementation of DifferentiableFunction that uses Function<T> by default.
+/// Any pair of closures works as well.
 #[derive(Copy, Clone, Debug, new)]
-pub struct SimpleDifferentiableFunction<T> {
+pub struct SimpleDifferentiableFunction<T, F = Function<T>, DF = Function<T>> {
+    _t: PhantomData<T>,
     /// f
-    pub f: Function<T>,
+    pub f: F,
     /// f'
-    pub df: Function<T>,
+    pub df: DF,
 }
 
-impl<T> SampleableFunction<T, f64> for SimpleDifferentiableFunction<T> {
+impl<T, F: Fn(T) -> f64, DF> SampleableFunction<T, f64> for SimpleDifferentiableFunction<T, F, DF> {
     fn value_at(&self, input: T) -> f64 {
         (self.f)(input)
     }
 }
 
-impl<T> DifferentiableFunction<T, f64> for SimpleDifferentiableFunction<T> {
+impl<T, F: Fn(T) -> f64, DF: Fn(T) -> f64> DifferentiableFunction<T, f64>
+    for SimpleDifferentiableFunction<T, F, DF>
+{
     fn derivative_at(&self, input: T) -> f64 {
         (self.df)(input)
     }
@@ -303,27 +333,397 @@ pub struct InitialValueProblem<FT> {
 
 impl InitialValueProblem<Function2D> {
     /// Helper to make multi dimensional euler usable for one dimensional problems
-    pub fn to_system_problem(
-        self,
-    ) -> InitialValueSystemProblem<ClosureSampleableFunction<(f64, Vec<f64>), Function2D>> {
-        let new_df: Closure<(f64, Vec<f64>), Function2D> = |(t, vec_x), df| df((t, vec_x[0]));
+    pub fn to_system_problem(self) -> InitialValueSystemProblem<impl OdeSystem + Clone> {
+        let df = self.df;
         InitialValueSystemProblem::new(
             self.start_time,
             vec![self.start_value],
-            vec![ClosureSampleableFunction::new(self.df, new_df)],
+            move |t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = df((t, y[0])),
         )
     }
 }
 
+/// Right hand side f(t, y) of a system of ODEs y' = f(t, y).
+/// All components are evaluated in one go, so shared subexpressions only have to be computed once.
+///
+/// Implemented for closures of the form `|t, y, dydt| ...` and for the per-component form,
+/// i.e. a vector with one function per component.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, ODEMethod};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// // Harmonic oscillator
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+///     dydt[0] = y[1];
+///     dydt[1] = -y[0];
+/// };
+/// let problem = InitialValueSystemProblem::new(0.0, vec![1.0, 0.0], rhs);
+///
+/// let solution = make_classic_runge_kutta(problem, 0.1).interval(3.14, 0).unwrap();
+/// assert!((solution.final_state().unwrap()[0] - 3.14f64.cos()).abs() < 1e-4);
+/// ```
+pub trait OdeSystem<N: Scalar = f64> {
+    /// Writes f(t, y) into dydt. Both slices have the same length.
+    fn rhs(&self, t: f64, y: &[N], dydt: &mut [N]);
+
+    /// Convenience version of `rhs` that allocates the result.
+    fn rhs_vec(&self, t: f64, y: &[N]) -> Vec<N> {
+        let mut dydt = vec![N::zero(); y.len()];
+        self.rhs(t, y, &mut dydt);
+        dydt
+    }
+
+    /// Convenience version of `rhs` for any state vector, allocates the result.
+    fn rhs_state<S: StateVector<Scalar = N>>(&self, t: f64, y: &S) -> S
+    where
+        Self: Sized,
+    {
+        let mut dydt = y.zeros_like();
+        self.rhs(t, y.as_slice(), dydt.as_mut_slice());
+        dydt
+    }
+
+    /// Number of equations, if the system knows it.
+    /// Used to check the start values before solving.
+    fn dimension(&self) -> Option<usize> {
+        None
+    }
+
+    /// Writes the Jacobian ∂f_i/∂y_j row by row into jac, which has y.len()^2 entries.
+    /// Approximated with forward differences unless the system knows it,
+    /// then `jacobian_rhs_evaluations` should be overridden as well.
+    fn jacobian(&self, t: f64, y: &[N], jac: &mut [N]) {
+        let dim = y.len();
+        let f = self.rhs_vec(t, y);
+        let mut y_shifted = y.to_vec();
+        let mut f_shifted = vec![N::zero(); dim];
+        for col in 0..dim {
+            let delta = sqrt!(f64::EPSILON) * y[col].modulus().max(1.0);
+            y_shifted[col] = y[col] + N::from_f64(delta);
+            self.rhs(t, &y_shifted, &mut f_shifted);
+            for row in 0..dim {
+                jac[row * dim + col] = (f_shifted[row] - f[row]) / N::from_f64(delta);
+            }
+            y_shifted[col] = y[col];
+        }
+    }
+
+    /// Writes ∂f/∂t into dfdt.
+    /// Approximated with a forward difference unless the system knows it,
+    /// then `time_derivative_rhs_evaluations` should be overridden as well.
+    fn time_derivative(&self, t: f64, y: &[N], dfdt: &mut [N]) {
+        let delta = sqrt!(f64::EPSILON) * abs!(t).max(1.0);
+        self.rhs(t + delta, y, dfdt);
+        for (d, f) in dfdt.iter_mut().zip(self.rhs_vec(t, y)) {
+            *d = (*d - f) / N::from_f64(delta);
+        }
+    }
+
+    /// Writes y'' = ∂f/∂t + ∂f/∂y f into d2ydt2, by default from `time_derivative` and `jacobian`.
+    /// Systems that know it should override `second_derivative_evaluations` as well.
+    fn second_derivative(&self, t: f64, y: &[N], d2ydt2: &mut [N]) {
+        let dim = y.len();
+        let f = self.rhs_vec(t, y);
+        let mut jac = vec![N::zero(); dim * dim];
+        self.jacobian(t, y, &mut jac);
+        self.time_derivative(t, y, d2ydt2);
+        for (d, row) in d2ydt2.iter_mut().zip(jac.chunks_exact(dim)) {
+            for (j, f_j) in row.iter().zip(&f) {
+                *d += *j * *f_j;
+            }
+        }
+    }
+
+    /// Evaluations of `rhs` one call of `jacobian` makes for a state with dim components,
+    /// so the solvers can count them. dim + 1 for the forward differences, 0 for a known Jacobian.
+    fn jacobian_rhs_evaluations(&self, dim: usize) -> usize {
+        dim + 1
+    }
+
+    /// Evaluations of `rhs` one call of `time_derivative` makes, 2 for the forward difference.
+    fn time_derivative_rhs_evaluations(&self) -> usize {
+        2
+    }
+
+    /// Evaluations of `rhs` and of `jacobian` one call of `second_derivative` makes for a state with dim components.
+    /// By default f itself, the Jacobian and the time derivative, (0, 0) for a known y''.
+    fn second_derivative_evaluations(&self, dim: usize) -> (usize, usize) {
+        let rhs_evaluations =
+            1 + self.jacobian_rhs_evaluations(dim) + self.time_derivative_rhs_evaluations();
+        (rhs_evaluations, 1)
+    }
+}
+
+impl<N: Scalar, F: Fn(f64, &[N], &mut [N])> OdeSystem<N> for F {
+    fn rhs(&self, t: f64, y: &[N], dydt: &mut [N]) {
+        self(t, y, dydt)
+    }
+}
+
+/// Adapter for the per-component form: every function gets the complete state and returns one component.
+impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> OdeSystem for Vec<FT> {
+    fn rhs(&self, t: f64, y: &[f64], dydt: &mut [f64]) {
+        let y_owned = y.to_vec();
+        for (d, df) in dydt.iter_mut().zip(self) {
+            *d = df.value_at((t, y_owned.clone()));
+        }
+    }
+
+    fn dimension(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+/// The number type the components of a state are made of.
+/// Time is always measured in f64, only the values of the solution use this type.
+///
+/// Implemented for `f32`, `f64` and the complex numbers based on them.
+/// Other types, e.g. double-double numbers for reference solutions, only have to implement this trait.
+///
+/// # Example
+/// ```
+/// use num::{Complex, Zero};
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, SampleableFunction};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// // y' = i * y, so y(t) = e^(i * t)
+/// let rhs = |_t: f64, y: &[Complex<f64>], dydt: &mut [Complex<f64>]| dydt[0] = Complex::<f64>::i() * y[0];
+/// let problem = InitialValueSystemProblem::new(0.0, vec![Complex::new(1.0, 0.0)], rhs);
+///
+/// let y = make_classic_runge_kutta(problem, 0.01).value_at(1.0);
+/// assert!((y[0] - Complex::new(1.0f64.cos(), 1.0f64.sin())).norm() < 1e-8);
+/// ```
+pub trait Scalar: Copy + Debug + NumAssign + Neg<Output = Self> + 'static {
+    /// Converts a real number, e.g. a step size, into this type.
+    fn from_f64(value: f64) -> Self;
+
+    /// numerator / denominator, calculated in this type.
+    /// Used for constants like 1/3, so they have the full precision of the type.
+    fn from_ratio(numerator: f64, denominator: f64) -> Self {
+        Self::from_f64(numerator) / Self::from_f64(denominator)
+    }
+
+    /// |self|, i.e. the modulus for complex numbers.
+    fn modulus(self) -> f64;
+
+    /// False for NaN and infinite values (in any part).
+    fn is_finite(self) -> bool;
+}
+
+impl Scalar for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn modulus(self) -> f64 {
+        abs!(self)
+    }
+
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
+
+impl Scalar for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn modulus(self) -> f64 {
+        f64::from(abs!(self))
+    }
+
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+}
+
+impl Scalar for Complex<f64> {
+    fn from_f64(value: f64) -> Self {
+        Complex::new(value, 0.0)
+    }
+
+    fn modulus(self) -> f64 {
+        self.norm()
+    }
+
+    fn is_finite(self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+}
+
+impl Scalar for Complex<f32> {
+    fn from_f64(value: f64) -> Self {
+        Complex::new(value as f32, 0.0)
+    }
+
+    fn modulus(self) -> f64 {
+        f64::from(self.norm())
+    }
+
+    fn is_finite(self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+}
+
+/// The state of a system of ODEs, i.e. an element of R^n (or C^n) the solvers can compute with.
+/// Implementors only have to expose their components as a contiguous slice,
+/// the vector space operations are derived from that, but can be overridden with faster versions.
+///
+/// Implemented for `Vec<N>`, `[N; M]` and nalgebra's `DVector<N>` and `VectorN<N, D>` of any `Scalar` N.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, SampleableFunction, StateVector};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// // Position and velocity of a falling body
+/// #[derive(Clone, Debug)]
+/// struct Body {
+///     data: [f64; 2],
+/// }
+///
+/// impl StateVector for Body {
+///     type Scalar = f64;
+///
+///     fn as_slice(&self) -> &[f64] {
+///         &self.data
+///     }
+///
+///     fn as_mut_slice(&mut self) -> &mut [f64] {
+///         &mut self.data
+///     }
+/// }
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+///     dydt[0] = y[1];
+///     dydt[1] = -9.81;
+/// };
+/// let problem = InitialValueSystemProblem::new(0.0, Body { data: [100.0, 0.0] }, rhs);
+///
+/// let body: Body = make_classic_runge_kutta(problem, 0.1).value_at(1.0);
+/// assert!((body.data[0] - (100.0 - 9.81 / 2.0)).abs() < 1e-10);
+/// ```
+pub trait StateVector: Clone {
+    /// Type of the components
+    type Scalar: Scalar;
+
+    /// The components
+    fn as_slice(&self) -> &[Self::Scalar];
+
+    /// The components, mutable
+    fn as_mut_slice(&mut self) -> &mut [Self::Scalar];
+
+    /// Number of components
+    fn dim(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// A vector of the same shape with all components set to zero.
+    fn zeros_like(&self) -> Self {
+        let mut zeros = self.clone();
+        zeros
+            .as_mut_slice()
+            .iter_mut()
+            .for_each(|v| *v = Self::Scalar::zero());
+        zeros
+    }
+
+    /// self = self + a * x
+    fn axpy(&mut self, a: Self::Scalar, x: &Self) {
+        self.as_mut_slice()
+            .iter_mut()
+            .zip(x.as_slice())
+            .for_each(|(v, x)| *v += a * *x);
+    }
+
+    /// self = a * self
+    fn scale(&mut self, a: Self::Scalar) {
+        self.as_mut_slice().iter_mut().for_each(|v| *v *= a);
+    }
+
+    /// Euclidean norm
+    fn norm(&self) -> f64 {
+        sqrt!(self
+            .as_slice()
+            .iter()
+            .map(|v| powi!(v.modulus(), 2))
+            .sum::<f64>())
+    }
+
+    /// True if no component is NaN or infinite.
+    fn is_finite(&self) -> bool {
+        self.as_slice().iter().all(|v| v.is_finite())
+    }
+}
+
+impl<N: Scalar> StateVector for Vec<N> {
+    type Scalar = N;
+
+    fn as_slice(&self) -> &[N] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [N] {
+        self
+    }
+
+    fn zeros_like(&self) -> Self {
+        vec![N::zero(); self.len()]
+    }
+}
+
+impl<N: Scalar, const M: usize> StateVector for [N; M] {
+    type Scalar = N;
+
+    fn as_slice(&self) -> &[N] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [N] {
+        self
+    }
+
+    fn zeros_like(&self) -> Self {
+        [N::zero(); M]
+    }
+}
+
+/// Covers `DVector<N>` as well as the statically sized vectors.
+impl<N: Scalar, D: Dim> StateVector for VectorN<N, D>
+where
+    DefaultAllocator: Allocator<N, D>,
+{
+    type Scalar = N;
+
+    fn as_slice(&self) -> &[N] {
+        Matrix::as_slice(self)
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [N] {
+        Matrix::as_mut_slice(self)
+    }
+
+    fn zeros_like(&self) -> Self {
+        self.map(|_| N::zero())
+    }
+
+    fn axpy(&mut self, a: N, x: &Self) {
+        Matrix::axpy(self, a, x, N::one())
+    }
+}
+
 /// Assume that values and dfs are same size and functions match...
 #[derive(Clone, Debug, new)]
-pub struct InitialValueSystemProblem<FT: SampleableFunction<(f64, Vec<f64>), f64>> {
+pub struct InitialValueSystemProblem<SYS: OdeSystem<S::Scalar>, S: StateVector = Vec<f64>> {
     /// t_0
     pub start_time: f64,
     /// f(t_0)
-    pub start_values: Vec<f64>,
+    pub start_values: S,
     /// f'(x)
-    pub dfs: Vec<FT>,
+    pub dfs: SYS,
 }
 
 /// Problem like in task 2 subtask 4.
@@ -342,13 +742,136 @@ pub struct BoundaryValueProblem {
 
 /// A ODE solving method that can be sampled for one value at t or all intermediate values as well.
 pub trait ODEMethod {
-    /// Note that the result is a vector over t of the values at the points of the inner vector.
-    fn interval(&self, t_target: f64, skip_n: isize) -> Vec<Vec<Point2D>>;
+    /// All states from the start up to t_target, only every skip_n-th step is kept.
+    fn interval(&self, t_target: f64, skip_n: isize) -> Result<Solution, SolverError>;
+
+    /// The states exactly at the output times and nowhere else.
+    fn interval_at(&self, output: &OutputTimes) -> Result<Solution, SolverError>;
+}
+
+/// Everything that can go wrong while solving a problem.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SolverError {
+    /// Newton's method did not reach the tolerance, e.g. because it diverged.
+    NewtonNotConverged {
+        /// Time of the step that needed the solve
+        t: f64,
+        /// Iterations done before giving up
+        iterations: usize,
+    },
+    /// An adaptive method had to reduce the step size below what can still change t.
+    StepSizeUnderflow {
+        /// Time the method got stuck at
+        t: f64,
+        /// Last step size
+        h: f64,
+    },
+    /// More steps than allowed were needed to reach the target.
+    MaxStepsExceeded {
+        /// Time reached so far
+        t: f64,
+        /// The allowed number of steps
+        max_steps: usize,
+    },
+    /// The state contains NaN or infinite values, usually because the problem blew up.
+    NonFiniteState {
+        /// First time with a non finite state
+        t: f64,
+    },
+    /// The number of start values does not match the number of equations, there are no start values at all,
+    /// or a per-component tolerance does not match the number of start values.
+    DimensionMismatch {
+        /// Number of equations, or of start values for tolerances
+        expected: usize,
+        /// Number of start values or tolerances
+        found: usize,
+    },
+    /// A linear system could not be solved.
+    SingularMatrix,
+    /// Events were combined with fixed output times, watching them needs every step.
+    EventsWithOutputTimes,
+    /// An output time is not finite or cannot be reached, e.g. because it lies on the other side
+    /// of the start time than the rest. Also reported for a zero output interval, with t = t_target.
+    InvalidOutputTime {
+        /// The offending output time
+        t: f64,
+    },
+    /// An observer asked the solver to stop.
+    Cancelled {
+        /// Time the solver stopped at
+        t: f64,
+    },
+    /// A solution sink failed to write a state.
+    Output {
+        /// Kind of the underlying io error
+        kind: std::io::ErrorKind,
+    },
+}
+
+impl Display for SolverError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            SolverError::NewtonNotConverged { t, iterations } => write!(
+                f,
+                "Newton's method did not converge after {} iterations at t = {}",
+                iterations, t
+            ),
+            SolverError::StepSizeUnderflow { t, h } => {
+                write!(f, "Step size {:E} too small at t = {}", h, t)
+            }
+            SolverError::MaxStepsExceeded { t, max_steps } => write!(
+                f,
+                "Maximum number of {} steps exceeded at t = {}",
+                max_steps, t
+            ),
+            SolverError::NonFiniteState { t } => write!(f, "NaN or infinite value at t = {}", t),
+            SolverError::DimensionMismatch { expected, found } => write!(
+                f,
+                "Expected {} components, but {} were given",
+                expected, found
+            ),
+            SolverError::SingularMatrix => f.write_str("Matrix is singular"),
+            SolverError::EventsWithOutputTimes => {
+                f.write_str("Events can only be watched if every step is output")
+            }
+            SolverError::InvalidOutputTime { t } => {
+                write!(f, "Output time {} cannot be reached from the start time", t)
+            }
+            SolverError::Cancelled { t } => write!(f, "Cancelled by the observer at t = {}", t),
+            SolverError::Output { kind } => write!(f, "Writing the solution failed: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+impl From<std::io::Error> for SolverError {
+    fn from(error: std::io::Error) -> Self {
+        SolverError::Output { kind: error.kind() }
+    }
 }
 
-impl<ODEM: ODEMethod> SampleableFunction<f64, Vec<f64>> for ODEM {
-    fn value_at(&self, t_target: f64) -> Vec<f64> {
-        let results = self.interval(t_target, 0);
-        results.last().unwrap().iter().map(|p| p.y).collect()
+/// Fails with `SolverError::DimensionMismatch` if the system knows its dimension and it does not fit the start values,
+/// or if there are no start values.
+pub(crate) fn check_dimension<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: &InitialValueSystemProblem<SYS, S>,
+) -> Result<(), SolverError> {
+    let found = ivp.start_values.dim();
+    match ivp.dfs.dimension() {
+        Some(expected) if expected != found => {
+            Err(SolverError::DimensionMismatch { expected, found })
+        }
+        // A state needs at least one component
+        _ if found == 0 => Err(SolverError::DimensionMismatch { expected: 1, found }),
+        _ => Ok(()),
+    }
+}
+
+/// Fails with `SolverError::NonFiniteState` if the state blew up.
+pub(crate) fn check_finite<S: StateVector>(t: f64, values: &S) -> Result<(), SolverError> {
+    if values.is_finite() {
+        Ok(())
+    } else {
+        Err(SolverError::NonFiniteState { t })
     }
 }
@@ -0,0 +1,153 @@
+use crate::definitions::{SampleableFunction, Scalar, SolverError};
+use crate::solution::Solution;
+use crate::stats::SolverStats;
+
+/// A solution that can be sampled at every t inside the integration range, not only at the grid points.
+/// Every step carries a polynomial in theta = (t - t_i) / h_i in the form used by Hairer's DOPRI5:
+///
+/// y(t_i + theta h_i) = y_i + theta (r2 + (1 - theta) (r3 + theta (r4 + (1 - theta) r5)))
+///
+/// Cubic Hermite interpolation is the special case r5 = 0.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, SampleableFunction};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+/// let problem = InitialValueSystemProblem::new(0.0, vec![1.0], rhs);
+///
+/// let dense = make_classic_runge_kutta(problem, 0.1).dense_interval(1.0).unwrap();
+///
+/// // Between the grid points
+/// assert!((dense.value_at(0.55)[0] - (-0.55f64).exp()).abs() < 1e-5);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DenseSolution<N: Scalar = f64> {
+    grid: Solution<N>,
+    // r2 to r5 for every step, row major
+    coeffs: Vec<N>,
+    order: usize,
+}
+
+impl<N: Scalar> DenseSolution<N> {
+    /// Starts a dense solution at the given start value.
+    /// `order` is the order of the interpolation polynomials.
+    pub fn new(t0: f64, y0: &[N], order: usize) -> Self {
+        let mut grid = Solution::new(y0.len());
+        grid.push(t0, y0);
+        DenseSolution {
+            grid,
+            coeffs: Vec::new(),
+            order,
+        }
+    }
+
+    /// Appends a step ending in (t1, y1) that is interpolated with a cubic Hermite polynomial.
+    /// `f0` and `f1` are the slopes at the start and the end of the step.
+    pub fn push_hermite(&mut self, t1: f64, y1: &[N], f0: &[N], f1: &[N]) {
+        let h = N::from_f64(t1 - self.final_time());
+        let y0 = self.final_state().to_vec();
+
+        let mut rs = vec![N::zero(); 4 * y1.len()];
+        for (idx, r) in rs.chunks_exact_mut(4).enumerate() {
+            let diff = y1[idx] - y0[idx];
+            r[0] = diff;
+            r[1] = h * f0[idx] - diff;
+            r[2] = diff - h * f1[idx] - r[1];
+        }
+        self.push_step(t1, y1, &rs);
+    }
+
+    /// Appends a step ending in (t1, y1).
+    /// `rs` holds r2, r3, r4, r5 for every component, i.e. `4 * dim` values.
+    ///
+    /// # Panics
+    /// If the number of coefficients does not match the dimension.
+    pub fn push_step(&mut self, t1: f64, y1: &[N], rs: &[N]) {
+        assert_eq!(
+            rs.len(),
+            4 * self.grid.dim(),
+            "Need four coefficients per component"
+        );
+        self.grid.push(t1, y1);
+        self.coeffs.extend_from_slice(rs);
+    }
+
+    /// The grid points the solver actually stepped on.
+    pub fn grid(&self) -> &Solution<N> {
+        &self.grid
+    }
+
+    /// The work the solver did to get this solution.
+    pub fn stats(&self) -> &SolverStats {
+        self.grid.stats()
+    }
+
+    pub(crate) fn set_stats(&mut self, stats: SolverStats) {
+        self.grid.set_stats(stats);
+    }
+
+    /// Order of the interpolation between the grid points.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// True if t lies inside the integration range.
+    pub fn contains(&self, t: f64) -> bool {
+        let start_time = self.grid.times()[0];
+        let final_time = self.final_time();
+        start_time.min(final_time) <= t && t <= start_time.max(final_time)
+    }
+
+    /// Like `value_at`, but reports a t outside of the integration range instead of panicking.
+    pub fn try_value_at(&self, t: f64) -> Result<Vec<N>, SolverError> {
+        if self.contains(t) {
+            Ok(self.value_at(t))
+        } else {
+            Err(SolverError::InvalidOutputTime { t })
+        }
+    }
+
+    fn final_time(&self) -> f64 {
+        // There always is at least the start value
+        self.grid.final_time().unwrap()
+    }
+
+    fn final_state(&self) -> &[N] {
+        self.grid.final_state().unwrap()
+    }
+}
+
+impl<N: Scalar> SampleableFunction<f64, Vec<N>> for DenseSolution<N> {
+    /// # Panics
+    /// If t lies outside of the integration range.
+    fn value_at(&self, t: f64) -> Vec<N> {
+        assert!(
+            self.contains(t),
+            "t = {} is outside of the integration range",
+            t
+        );
+        let ts = self.grid.times();
+        if ts.len() == 1 {
+            return self.grid.state(0).to_vec();
+        }
+
+        // The step ending in the first grid point at or past t
+        let forward = ts[0] <= self.final_time();
+        let step = ts
+            .partition_point(|t_i| if forward { *t_i < t } else { *t_i > t })
+            .max(1)
+            - 1;
+        let theta = N::from_f64((t - ts[step]) / (ts[step + 1] - ts[step]));
+        let theta1 = N::one() - theta;
+
+        let dim = self.grid.dim();
+        self.grid
+            .state(step)
+            .iter()
+            .zip(self.coeffs[4 * dim * step..4 * dim * (step + 1)].chunks_exact(4))
+            .map(|(y, r)| *y + theta * (r[0] + theta1 * (r[1] + theta * (r[2] + theta1 * r[3]))))
+            .collect()
+    }
+}
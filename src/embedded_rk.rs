@@ -1,195 +1,1046 @@
-use crate::definitions::{InitialValueSystemProblem, Point2D, SampleableFunction};
-use crate::explicit_runge_kutta::{make_explicit_runge_kutta_with_tableau, Tableau};
-use crate::{abs, powf};
+use crate::abs;
+use crate::definitions::{
+    check_dimension, check_finite, InitialValueSystemProblem, OdeSystem, SolverError, StateVector,
+};
+use crate::dense_output::DenseSolution;
+use crate::events::{Event, EventDetector, EventOccurrence, SolutionWithEvents};
+use crate::explicit_runge_kutta::Tableau;
+use crate::method_info::MethodInfo;
+use crate::observer::{proceed, Observer, ProgressReporter, StepsOnly};
+use crate::sink::SolutionSink;
+use crate::solution::{OutputTimes, Solution};
+use crate::stats::SolverStats;
+use crate::step_size_controller::{initial_step_size, IController, StepSizeController};
+use crate::stepper::Stepper;
+use crate::tolerance::{ErrorControl, ErrorNorm};
+use crate::util::{before_target, directed_step};
 use derive_new::*;
+use num::complex::Complex64;
 use std::marker::PhantomData;
 
-const PRINT_NUM: isize = 500;
+/// Accepted steps after which the integration is aborted, unless configured otherwise.
+pub(crate) const MAX_STEPS: usize = 1_000_000;
 
-/// Implementation for an embedded RK method with only explicit components.
-/// HACK: Only looks at first component for error
+/// Generates a fresh copy of the problem for every run.
+pub type ProblemGenerator<SYS> = Box<dyn Fn() -> InitialValueSystemProblem<SYS>>;
+
+/// The two tableaus of an embedded method, the difference of their results estimates the error.
 #[derive(Clone, Debug, new)]
-pub struct EmbeddedExplicitRungeKuttaMethod<FT: SampleableFunction<(f64, Vec<f64>), f64>> {
-    _t: PhantomData<FT>,
+pub struct EmbeddedTableau {
     tableau: Tableau,
-    tableau_lower: Tableau,
     // the one with the lower order
+    tableau_lower: Tableau,
     lower_order: usize,
-    current_h: f64,
-    make_ivp: fn() -> InitialValueSystemProblem<FT>,
-    tolerance: f64,
+    // Weights of the stages for the native dense output, see `dense_interval`
+    #[new(default)]
+    dense_weights: Option<Vec<f64>>,
 }
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> EmbeddedExplicitRungeKuttaMethod<FT> {
-    fn step(&mut self, t: f64, last_values: &[f64]) -> Vec<f64> {
-        let rk1 = make_explicit_runge_kutta_with_tableau(
-            (self.make_ivp)(),
-            self.current_h,
-            self.tableau.clone(),
-        );
-        let val1 = rk1.value_at(t + self.current_h);
+impl EmbeddedTableau {
+    /// Heun/Euler pair of order 2(1).
+    pub fn heun_euler() -> Self {
+        EmbeddedTableau::new(
+            Tableau::new(
+                vec![0.0, 1.0], // cs
+                vec![0.5, 0.5], // bs
+                vec![vec![], vec![1.0]],
+            )
+            .with_name("Heun-Euler 2(1)"),
+            Tableau::new(
+                vec![0.0, 1.0], // cs
+                vec![1.0, 0.0], // bs
+                vec![vec![], vec![1.0]],
+            ),
+            1,
+        )
+    }
+
+    /// Dormand/Prince pair of order 5(4) with a 4th order dense output.
+    pub fn dopri5() -> Self {
+        let cs = vec![
+            vec![],
+            vec![0.2],
+            vec![3.0 / 40.0, 9.0 / 40.0],
+            vec![44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+            vec![
+                19372.0 / 6561.0,
+                -25360.0 / 2187.0,
+                64448.0 / 6561.0,
+                -212.0 / 729.0,
+            ],
+            vec![
+                9017.0 / 3168.0,
+                -355.0 / 33.0,
+                46732.0 / 5247.0,
+                49.0 / 176.0,
+                -5103.0 / 18656.0,
+            ],
+            vec![
+                35.0 / 384.0,
+                0.0,
+                500.0 / 1113.0,
+                125.0 / 192.0,
+                -2187.0 / 6784.0,
+                11.0 / 84.0,
+            ],
+        ];
 
-        let rk2 = make_explicit_runge_kutta_with_tableau(
-            (self.make_ivp)(),
-            self.current_h,
-            self.tableau_lower.clone(),
+        let mut tableau = EmbeddedTableau::new(
+            Tableau::new(
+                vec![0.0, 0.2, 0.3, 0.8, 8.0 / 9.0, 1.0, 1.0], // cs
+                vec![
+                    35.0 / 384.0,
+                    0.0,
+                    500.0 / 1113.0,
+                    125.0 / 192.0,
+                    -2187.0 / 6784.0,
+                    11.0 / 84.0,
+                    0.0,
+                ], // bs
+                cs.clone(),
+            )
+            .with_name("Dormand-Prince 5(4)"),
+            Tableau::new(
+                vec![0.0, 0.2, 0.3, 0.8, 8.0 / 9.0, 1.0, 1.0], // cs
+                vec![
+                    5179.0 / 57600.0,
+                    0.0,
+                    7571.0 / 16695.0,
+                    393.0 / 640.0,
+                    -92097.0 / 339200.0,
+                    187.0 / 2100.0,
+                    1.0 / 40.0,
+                ], // bs
+                cs,
+            ),
+            4,
         );
-        let val2 = rk2.value_at(t + self.current_h);
+        tableau.dense_weights = Some(vec![
+            -12715105075.0 / 11282082432.0,
+            0.0,
+            87487479700.0 / 32700410799.0,
+            -10690763975.0 / 1880347072.0,
+            701980252875.0 / 199316789632.0,
+            -1453857185.0 / 822651844.0,
+            69997945.0 / 29380423.0,
+        ]);
+        tableau
+    }
+
+    /// Order of the interpolation between the steps, see `push_dense_step`.
+    fn dense_order(&self) -> usize {
+        if self.dense_weights.is_some() {
+            4
+        } else {
+            3
+        }
+    }
+
+    /// Extends `dense` by the step that ended in (t, values), built from the stages ks of that step.
+    /// Uses the native dense output if there is one and cubic Hermite interpolation otherwise.
+    /// The first stage is the slope at the start of the step and for FSAL methods the last one the slope at its end,
+    /// otherwise that costs one more evaluation of f, counted in `stats`.
+    fn push_dense_step<SYS: OdeSystem>(
+        &self,
+        system: &SYS,
+        dense: &mut DenseSolution,
+        t: f64,
+        values: &[f64],
+        ks: &[Vec<f64>],
+        stats: &mut SolverStats,
+    ) {
+        let slope = if self.tableau.is_fsal() {
+            ks[ks.len() - 1].clone()
+        } else {
+            stats.rhs_evaluations += 1;
+            system.rhs_vec(t, values)
+        };
+        match &self.dense_weights {
+            None => dense.push_hermite(t, values, &ks[0], &slope),
+            Some(ds) => {
+                let t0 = dense.grid().final_time().unwrap();
+                let y0 = dense.grid().final_state().unwrap();
+                let h = t - t0;
+
+                // Hairer, Norsett, Wanner: Solving ODEs I, section II.6
+                let mut rs = vec![0.0; 4 * values.len()];
+                for (idx, r) in rs.chunks_exact_mut(4).enumerate() {
+                    let diff = values[idx] - y0[idx];
+                    r[0] = diff;
+                    r[1] = h * ks[0][idx] - diff;
+                    r[2] = diff - h * slope[idx] - r[1];
+                    r[3] = h * ds.iter().zip(ks).map(|(d, k)| d * k[idx]).sum::<f64>();
+                }
+                dense.push_step(t, values, &rs);
+            }
+        }
+    }
+}
+
+/// Describes the higher order method, which is the one that advances the solution.
+impl MethodInfo for EmbeddedTableau {
+    fn name(&self) -> &str {
+        self.tableau.name()
+    }
+
+    fn order(&self) -> usize {
+        self.tableau.order()
+    }
+
+    /// Both tableaus share their stages
+    fn stages(&self) -> usize {
+        self.tableau.stages()
+    }
+
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        self.tableau.stability_function(z)
+    }
+}
+
+/// Implementation for an embedded RK method with only explicit components.
+/// A step is accepted if the error estimate is small enough for the `ErrorControl`.
+#[derive(new)]
+pub struct EmbeddedExplicitRungeKuttaMethod<SYS: OdeSystem> {
+    _t: PhantomData<SYS>,
+    tableau: EmbeddedTableau,
+    h_start: f64,
+    // Whether the first step size of every run is estimated instead of taken from h_start
+    #[new(default)]
+    automatic_h: bool,
+    make_ivp: ProblemGenerator<SYS>,
+    pub(crate) error_control: ErrorControl,
+    #[new(value = "Box::new(IController::default())")]
+    pub(crate) controller: Box<dyn StepSizeController>,
+    #[new(value = "0.0")]
+    pub(crate) h_min: f64,
+    #[new(value = "f64::INFINITY")]
+    pub(crate) h_max: f64,
+    #[new(value = "MAX_STEPS")]
+    pub(crate) max_steps: usize,
+}
+
+/// The direction, end and step size of a run and the buffers of the stages and results, reused from step to step.
+struct EmbeddedWorkspace {
+    // Sign of the steps
+    direction: f64,
+    // Size of the next try, adapted by the controller, always positive
+    h: f64,
+    // The steps are clipped to end exactly there, None for open-ended runs
+    t_end: Option<f64>,
+    // The stages followed by the arguments of the right-hand side
+    buffers: Vec<Vec<f64>>,
+    next_values: Vec<f64>,
+    // Difference of the results of the two tableaus
+    error: Vec<f64>,
+    // Whether the first stage belongs to the current state, e.g. from the last stage of a FSAL step
+    first_stage_known: bool,
+    // Whether the last stage of the accepted FSAL step still has to become the first one of the next step,
+    // until then the stages of the accepted step stay in order
+    fsal_pending: bool,
+}
+
+impl EmbeddedWorkspace {
+    fn new(direction: f64, h: f64, t_end: Option<f64>, stages: usize, dim: usize) -> Self {
+        EmbeddedWorkspace {
+            direction,
+            h,
+            t_end,
+            buffers: vec![vec![0.0; dim]; stages + 1],
+            next_values: vec![0.0; dim],
+            error: vec![0.0; dim],
+            first_stage_known: false,
+            fsal_pending: false,
+        }
+    }
+
+    /// The stages of the last accepted step.
+    fn stages(&self) -> &[Vec<f64>] {
+        &self.buffers[..self.buffers.len() - 1]
+    }
+}
+
+impl<SYS: OdeSystem> EmbeddedExplicitRungeKuttaMethod<SYS> {
+    /// Uses the tolerances and norm of the error control instead of the tolerance of the constructor.
+    /// Runs fail with `SolverError::DimensionMismatch` if a per-component tolerance does not have one value per component.
+    ///
+    /// # Example
+    /// ```
+    /// use ngdl_rust::definitions::InitialValueSystemProblem;
+    /// use ngdl_rust::embedded_rk::make_dopri5;
+    /// use ngdl_rust::tolerance::{ErrorControl, ErrorNorm};
+    ///
+    /// // Components of very different sizes need their own absolute tolerances
+    /// let create_problem = || {
+    ///     let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+    ///         dydt[0] = -y[0];
+    ///         dydt[1] = -2.0 * y[1];
+    ///     };
+    ///     InitialValueSystemProblem::new(0.0, vec![1e4, 1e-6], rhs)
+    /// };
+    /// let control = ErrorControl::new(vec![1e-2, 1e-12], 1e-6).with_norm(ErrorNorm::Rms);
+    ///
+    /// let solution = make_dopri5(create_problem, 0.1, 0.0)
+    ///     .with_error_control(control)
+    ///     .interval(1.0, 0)
+    ///     .unwrap();
+    ///
+    /// let (t, y) = (solution.final_time().unwrap(), solution.final_state().unwrap());
+    /// assert!((y[1] - 1e-6 * (-2.0 * t).exp()).abs() < 1e-12);
+    /// ```
+    pub fn with_error_control(mut self, error_control: ErrorControl) -> Self {
+        self.error_control = error_control;
+        self
+    }
+
+    /// Chooses the step sizes with the controller instead of the elementary one.
+    pub fn with_controller<C: StepSizeController + 'static>(mut self, controller: C) -> Self {
+        self.controller = Box::new(controller);
+        self
+    }
+
+    /// Estimates the first step size of every run with `initial_step_size` instead of using h_start.
+    ///
+    /// # Example
+    /// ```
+    /// use ngdl_rust::definitions::InitialValueSystemProblem;
+    /// use ngdl_rust::embedded_rk::make_dopri5;
+    ///
+    /// let create_problem = || {
+    ///     let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -1000.0 * (y[0] - 1.0);
+    ///     InitialValueSystemProblem::new(0.0, vec![0.0], rhs)
+    /// };
+    ///
+    /// // h_start is far too large, the estimate avoids the rejections
+    /// let solution = make_dopri5(create_problem, 1.0, 1e-6)
+    ///     .with_automatic_initial_step()
+    ///     .interval(0.01, 0)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(solution.stats().rejected_steps, 0);
+    /// ```
+    pub fn with_automatic_initial_step(mut self) -> Self {
+        self.automatic_h = true;
+        self
+    }
 
-        let err = val1.iter().zip(val2.iter()).zip(last_values.iter())
-            .map(|((v1, v2), l)| abs!(v1 - v2) / (1.0 + abs!(l)))
-            .fold(0.0, f64::max);
+    /// The first step size of a run towards t_target, h_start or the estimate if that is configured.
+    /// The configured h_start stays untouched, so every run starts the same.
+    /// An empty run takes no step, so nothing is estimated for it.
+    fn initial_h(
+        &self,
+        ivp: &InitialValueSystemProblem<SYS>,
+        t_target: f64,
+        stats: &mut SolverStats,
+    ) -> f64 {
+        if self.automatic_h && t_target != ivp.start_time {
+            stats.rhs_evaluations += 2;
+            initial_step_size(
+                &ivp.dfs,
+                ivp.start_time,
+                &ivp.start_values,
+                t_target,
+                self.tableau.order(),
+                &self.error_control,
+            )
+        } else {
+            self.h_start
+        }
+    }
+
+    /// Takes one accepted step from (t, values), overwrites values with the result and returns the new time.
+    /// The stages are evaluated once and shared by both tableaus, rejected tries reuse the first one.
+    /// The steps go in the direction of the workspace, negative to go backwards in time.
+    /// A try that would pass the end of the workspace is shortened to end exactly there.
+    /// Every accepted and rejected try is reported to the observer and counted in `stats`.
+    fn step(
+        &mut self,
+        system: &SYS,
+        t: f64,
+        values: &mut Vec<f64>,
+        workspace: &mut EmbeddedWorkspace,
+        observer: &mut dyn Observer,
+        stats: &mut SolverStats,
+    ) -> Result<f64, SolverError> {
+        let tableau = &self.tableau.tableau;
+        let stages = tableau.stages();
+        if workspace.fsal_pending {
+            // The last stage is f(t, values)
+            workspace.buffers.swap(0, stages - 1);
+            workspace.fsal_pending = false;
+        }
+        loop {
+            workspace.h = workspace.h.min(self.h_max);
+            // Once h does not change t anymore, trying even smaller steps is pointless
+            if workspace.h <= f64::EPSILON * abs!(t).max(1.0) || workspace.h < self.h_min {
+                return Err(SolverError::StepSizeUnderflow { t, h: workspace.h });
+            }
 
-        self.current_h = 2.0f64.min(
-            0.5f64
-                .max(0.9 * powf!(self.tolerance / err, 1.0 / (self.lower_order as f64 + 1.0))),
-        ) * self.current_h;
+            let remaining = workspace
+                .t_end
+                .map_or(f64::INFINITY, |t_end| abs!(t_end - t));
+            let clipped = workspace.h >= remaining;
+            let h_try = if clipped { remaining } else { workspace.h };
+            let h = workspace.direction * h_try;
+            let known_stages = if workspace.first_stage_known { 1 } else { 0 };
+            tableau.evaluate_stages_into(
+                system,
+                t,
+                values,
+                h,
+                &mut workspace.buffers,
+                known_stages,
+            );
+            stats.rhs_evaluations += stages - known_stages;
+            workspace.first_stage_known = true;
 
-        if err <= self.tolerance {
-            return val1;
+            workspace.next_values.copy_from_slice(values);
+            workspace.error.iter_mut().for_each(|e| *e = 0.0);
+            let weights = tableau
+                .weights()
+                .iter()
+                .zip(self.tableau.tableau_lower.weights());
+            for ((b, b_lower), k) in weights.zip(&workspace.buffers) {
+                workspace.next_values.axpy(h * b, k);
+                workspace.error.axpy(h * (b - b_lower), k);
+            }
+            check_finite(t + h, &workspace.next_values)?;
+
+            let err = self.error_control.scaled_error(
+                &mut workspace.error,
+                values,
+                &workspace.next_values,
+            );
+
+            workspace.h = self
+                .controller
+                .next_step_size(h_try, err, self.tableau.lower_order);
+
+            if err <= 1.0 {
+                std::mem::swap(values, &mut workspace.next_values);
+                if tableau.is_fsal() {
+                    workspace.fsal_pending = true;
+                } else {
+                    workspace.first_stage_known = false;
+                }
+                // t + h may be off by rounding
+                let t_next = match workspace.t_end {
+                    Some(t_end) if clipped => t_end,
+                    _ => t + h,
+                };
+                stats.accept(h);
+                proceed(observer.accepted(t_next, h, values), t_next)?;
+                return Ok(t_next);
+            }
+            stats.reject();
+            proceed(observer.rejected(t, h, err), t)?;
         }
-        self.step(t, last_values)
     }
 
-    pub fn interval(&mut self, t_target: f64, skip_n: isize) -> Vec<Vec<Point2D>> {
+    /// Integrates up to t_target with adaptive step sizes and hands every skip_n-th value to `output`,
+    /// together with the stages of the step that ended there, none for the start value and events.
+    /// t_target may also lie before the start time, then the steps go backwards.
+    /// The last step is shortened to land exactly on t_target, the start value and the value there are always passed on.
+    /// Stops early at the first terminal event, its state is the last one passed on.
+    /// Fails if the step size underflows or more than `MAX_STEPS` steps are needed, `solve` can change the limit.
+    /// The observer sees every accepted and rejected step and every value passed on, the work is counted in `stats`.
+    fn integrate(
+        &mut self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event],
+        observer: &mut dyn Observer,
+        stats: &mut SolverStats,
+        mut output: impl FnMut(f64, &[f64], &[Vec<f64>]) -> Result<(), SolverError>,
+    ) -> Result<Vec<EventOccurrence>, SolverError> {
         let ivp = (self.make_ivp)();
+        check_dimension(&ivp)?;
+        check_finite(ivp.start_time, &ivp.start_values)?;
+        let mut steps: usize = 0;
         let mut skip: isize = skip_n;
-        let mut print_cnt: isize = PRINT_NUM;
+        let direction = directed_step(1.0, ivp.start_time, t_target);
         let mut t = ivp.start_time;
+        self.error_control.check_dimension(ivp.start_values.len())?;
+        self.controller.reset();
+        let h = self.initial_h(&ivp, t_target, stats);
         let mut values = ivp.start_values.clone();
-        let mut intermediate_values: Vec<Vec<Point2D>> = Vec::new(); // Can't predict steps due to variability
+        let mut workspace = EmbeddedWorkspace::new(
+            direction,
+            h,
+            Some(t_target),
+            self.tableau.stages(),
+            values.len(),
+        );
+        let mut detector = EventDetector::watch(events, t, &values);
 
-        intermediate_values.push(values.iter().map(|val| Point2D { x: t, y: *val }).collect());
+        let mut output =
+            |observer: &mut dyn Observer, t: f64, values: &[f64], stages: &[Vec<f64>]| {
+                proceed(observer.output(t, values), t)?;
+                output(t, values, stages)
+            };
+        output(observer, t, &values, &[])?;
 
-        // We overshoot, but that can't be helped
-        while t < t_target {
-            values = self.step(t, &values);
+        while before_target(t, t_target, direction) {
+            if steps == self.max_steps {
+                return Err(SolverError::MaxStepsExceeded {
+                    t,
+                    max_steps: self.max_steps,
+                });
+            }
+            t = self.step(&ivp.dfs, t, &mut values, &mut workspace, observer, stats)?;
+            steps += 1;
 
-            t += self.current_h;
+            if let Some(detector) = detector.as_mut() {
+                // Located with the same interpolant as the dense output
+                let tableau = &self.tableau;
+                let occurrence =
+                    detector.check_interpolated_step(t, &values, |t_last, values_last| {
+                        let mut step =
+                            DenseSolution::new(t_last, values_last, tableau.dense_order());
+                        tableau.push_dense_step(
+                            &ivp.dfs,
+                            &mut step,
+                            t,
+                            &values,
+                            workspace.stages(),
+                            stats,
+                        );
+                        step
+                    });
+                if let Some(occurrence) = occurrence {
+                    output(observer, occurrence.t, &occurrence.state, &[])?;
+                    return Ok(detector.take_occurrences());
+                }
+            }
             skip -= 1;
-            if skip <= 0 {
-                intermediate_values
-                    .push(values.iter().map(|val| Point2D { x: t, y: *val }).collect());
+            if skip <= 0 || t == t_target {
+                output(observer, t, &values, workspace.stages())?;
                 skip = skip_n
             }
+        }
+        Ok(detector.map_or_else(Vec::new, |mut detector| detector.take_occurrences()))
+    }
+
+    /// Steps through the problem one accepted step at a time, see `Stepper`.
+    pub fn stepper(&mut self) -> Result<EmbeddedStepper<'_, SYS>, SolverError> {
+        let ivp = (self.make_ivp)();
+        check_dimension(&ivp)?;
+        check_finite(ivp.start_time, &ivp.start_values)?;
+        self.error_control.check_dimension(ivp.start_values.len())?;
+        self.controller.reset();
+        // The stepper has no end, it goes forwards
+        let mut stats = SolverStats::default();
+        let h = self.initial_h(&ivp, f64::INFINITY, &mut stats);
+        let workspace =
+            EmbeddedWorkspace::new(1.0, h, None, self.tableau.stages(), ivp.start_values.len());
+        Ok(EmbeddedStepper {
+            method: self,
+            system: ivp.dfs,
+            workspace,
+            t: ivp.start_time,
+            values: ivp.start_values,
+            error: None,
+            stats,
+        })
+    }
+
+    /// Approximates the solution up to t_target with adaptive step sizes.
+    /// Only every skip_n-th step is returned, but the solution always ends exactly at t_target.
+    /// Fails if the step size underflows or more than `MAX_STEPS` steps are needed, `solve` can change the limit.
+    ///
+    /// # Example
+    /// ```
+    /// use ngdl_rust::definitions::InitialValueSystemProblem;
+    /// use ngdl_rust::embedded_rk::make_dopri5;
+    ///
+    /// let create_problem = || {
+    ///     let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+    ///     InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+    /// };
+    ///
+    /// let solution = make_dopri5(create_problem, 0.3, 1e-8).interval(2.0, 5).unwrap();
+    ///
+    /// assert_eq!(solution.final_time(), Some(2.0));
+    /// assert!((solution.final_state().unwrap()[0] - (-2.0f64).exp()).abs() < 1e-6);
+    /// ```
+    pub fn interval(&mut self, t_target: f64, skip_n: isize) -> Result<Solution, SolverError> {
+        self.interval_observed(t_target, skip_n, &mut ProgressReporter::default())
+    }
+
+    /// Like `interval`, but watches for the zero crossings of the events.
+    /// Returns the solution together with all located crossings.
+    /// If a terminal event occurs, the solution ends with the state at that event.
+    pub fn interval_with_events(
+        &mut self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event],
+    ) -> Result<SolutionWithEvents, SolverError> {
+        self.run(t_target, skip_n, events, &mut ProgressReporter::default())
+    }
+
+    /// Like `interval`, but reports every accepted and rejected step to the observer,
+    /// which may also cancel the run.
+    pub fn interval_observed<O: Observer>(
+        &mut self,
+        t_target: f64,
+        skip_n: isize,
+        observer: &mut O,
+    ) -> Result<Solution, SolverError> {
+        Ok(self.run(t_target, skip_n, &[], observer)?.0)
+    }
+
+    /// Collects every skip_n-th state up to t_target, the common part of the `interval` variants.
+    pub(crate) fn run(
+        &mut self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event],
+        observer: &mut dyn Observer,
+    ) -> Result<SolutionWithEvents, SolverError> {
+        let mut intermediate_values: Option<Solution> = None; // Can't predict steps due to variability
+        let mut stats = SolverStats::default();
+        let occurrences = self.integrate(
+            t_target,
+            skip_n,
+            events,
+            observer,
+            &mut stats,
+            |t, values, _| {
+                intermediate_values
+                    .get_or_insert_with(|| Solution::new(values.len()))
+                    .push(t, values);
+                Ok(())
+            },
+        )?;
+        // There always is at least the start value
+        let mut solution = intermediate_values.unwrap();
+        solution.set_stats(stats);
+        Ok((solution, occurrences))
+    }
+
+    /// Like `interval`, but hands the values to `sink` while stepping instead of collecting them,
+    /// so long runs need only constant memory.
+    /// Returns the stats of the run, as there is no solution to attach them to.
+    pub fn interval_into<K: SolutionSink>(
+        &mut self,
+        t_target: f64,
+        skip_n: isize,
+        sink: &mut K,
+    ) -> Result<SolverStats, SolverError> {
+        let mut stats = SolverStats::default();
+        self.integrate(
+            t_target,
+            skip_n,
+            &[],
+            &mut ProgressReporter::default(),
+            &mut stats,
+            |t, values, _| Ok(sink.write_state(t, values)?),
+        )?;
+        sink.finish()?;
+        Ok(stats)
+    }
+
+    /// The states exactly at the output times and nowhere else, interpolated with the dense output.
+    pub fn interval_at(&mut self, output: &OutputTimes) -> Result<Solution, SolverError> {
+        self.run_at(output, &mut ProgressReporter::default())
+    }
+
+    /// The common part of `interval_at`, the observer also sees the states at the output times.
+    pub(crate) fn run_at(
+        &mut self,
+        output: &OutputTimes,
+        observer: &mut dyn Observer,
+    ) -> Result<Solution, SolverError> {
+        let ivp = (self.make_ivp)();
+        check_dimension(&ivp)?;
+        let times = output.times(ivp.start_time)?;
+        let mut solution = Solution::with_capacity(ivp.start_values.len(), times.len());
+        if let Some(t_last) = times.last() {
+            let dense = self.dense_run(*t_last, observer)?;
+            for t in times {
+                let values = dense.try_value_at(t)?;
+                proceed(observer.output(t, &values), t)?;
+                solution.push(t, &values);
+            }
+            solution.set_stats(*dense.stats());
+        }
+        Ok(solution)
+    }
+
+    /// Approximates the solution up to t_target and keeps an interpolant for every step,
+    /// so the result can be sampled at any t in between.
+    /// Uses the native dense output of the method if it has one (4th order for DOPRI5)
+    /// and cubic Hermite interpolation otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use ngdl_rust::definitions::{InitialValueSystemProblem, SampleableFunction};
+    /// use ngdl_rust::embedded_rk::make_dopri5;
+    ///
+    /// let create_problem = || {
+    ///     let rhs = |t: f64, _y: &[f64], dydt: &mut [f64]| dydt[0] = t.cos();
+    ///     InitialValueSystemProblem::new(0.0, vec![0.0], rhs)
+    /// };
+    ///
+    /// let dense = make_dopri5(create_problem, 0.1, 1e-8).dense_interval(2.0).unwrap();
+    ///
+    /// assert_eq!(dense.order(), 4);
+    /// assert!((dense.value_at(1.234)[0] - 1.234f64.sin()).abs() < 1e-6);
+    /// ```
+    pub fn dense_interval(&mut self, t_target: f64) -> Result<DenseSolution, SolverError> {
+        self.dense_run(t_target, &mut ProgressReporter::default())
+    }
+
+    /// `dense_interval` with an observer for the steps, the grid points are not reported as output.
+    fn dense_run(
+        &mut self,
+        t_target: f64,
+        observer: &mut dyn Observer,
+    ) -> Result<DenseSolution, SolverError> {
+        let system = (self.make_ivp)().dfs;
+        let tableau = self.tableau.clone();
+
+        let mut dense: Option<DenseSolution> = None;
+        let mut stats = SolverStats::default();
+        let mut extra = SolverStats::default();
+        self.integrate(
+            t_target,
+            1,
+            &[],
+            &mut StepsOnly(observer),
+            &mut stats,
+            |t, values, ks| {
+                match dense.as_mut() {
+                    None => dense = Some(DenseSolution::new(t, values, tableau.dense_order())),
+                    Some(dense) => {
+                        tableau.push_dense_step(&system, dense, t, values, ks, &mut extra)
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        stats.add_work(&extra);
+        // There always is at least the start value
+        let mut dense = dense.unwrap();
+        dense.set_stats(stats);
+        Ok(dense)
+    }
+}
 
-            print_cnt -= 1;
-            if print_cnt <= 0 {
-                print_cnt = PRINT_NUM;
-                println!("Current t: {}\nCurrent h: {:E}\n", t, self.current_h);
+/// Iterator over the accepted steps of an embedded method, see `Stepper`.
+/// h is the proposal for the next step, the controller adapts it after every step.
+pub struct EmbeddedStepper<'a, SYS: OdeSystem> {
+    method: &'a mut EmbeddedExplicitRungeKuttaMethod<SYS>,
+    system: SYS,
+    workspace: EmbeddedWorkspace,
+    t: f64,
+    values: Vec<f64>,
+    error: Option<SolverError>,
+    stats: SolverStats,
+}
+
+impl<SYS: OdeSystem> Iterator for EmbeddedStepper<'_, SYS> {
+    type Item = (f64, Vec<f64>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.method.step(
+            &self.system,
+            self.t,
+            &mut self.values,
+            &mut self.workspace,
+            &mut ProgressReporter::default(),
+            &mut self.stats,
+        ) {
+            Ok(t) => {
+                self.t = t;
+                Some((self.t, self.values.clone()))
+            }
+            Err(err) => {
+                self.error = Some(err);
+                None
             }
         }
-        intermediate_values
     }
 }
 
+impl<SYS: OdeSystem> Stepper for EmbeddedStepper<'_, SYS> {
+    fn t(&self) -> f64 {
+        self.t
+    }
+
+    fn state(&self) -> &Vec<f64> {
+        &self.values
+    }
+
+    fn set_state(&mut self, values: Vec<f64>) {
+        self.values = values;
+        self.workspace.first_stage_known = false;
+        self.workspace.fsal_pending = false;
+        self.method.controller.reset();
+    }
+
+    fn h(&self) -> f64 {
+        self.workspace.direction * self.workspace.h
+    }
+
+    fn set_h(&mut self, h: f64) {
+        self.workspace.direction = h.signum();
+        self.workspace.h = h.abs();
+    }
+
+    fn error(&self) -> Option<SolverError> {
+        self.error
+    }
+
+    fn stats(&self) -> SolverStats {
+        self.stats
+    }
+}
+
+/// Creates a new embedded Runge-Kutta method for the given tableaus.
+/// `tableau1` is the one with the higher order, `lower_order` refers to `tableau2`.
+/// The stages are shared, so only the weights of `tableau2` are used.
 pub fn make_embedded_explicit_runge_kutta_with_tableau<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
+    SYS: OdeSystem,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS> + 'static,
 >(
-    create_ivp: fn() -> InitialValueSystemProblem<FT>,
+    create_ivp: IvpGen,
     h_start: f64,
     tableau1: Tableau,
     tableau2: Tableau,
     lower_order: usize,
     tolerance: f64,
-) -> EmbeddedExplicitRungeKuttaMethod<FT> {
-    EmbeddedExplicitRungeKuttaMethod::new(
-        tableau1,
-        tableau2,
-        lower_order,
-        h_start,
+) -> EmbeddedExplicitRungeKuttaMethod<SYS> {
+    make_embedded_runge_kutta(
         create_ivp,
+        h_start,
+        EmbeddedTableau::new(tableau1, tableau2, lower_order),
         tolerance,
     )
 }
 
-pub fn make_embedded_rk_1st_order<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    create_ivp: fn() -> InitialValueSystemProblem<FT>,
+/// Creates a new embedded Runge-Kutta method for the given pair of tableaus.
+/// A step is accepted if |y_i - ŷ_i| <= tolerance (1 + |y_i|) for every component,
+/// i.e. the tolerance is both absolute and relative, see `with_error_control` for other choices.
+pub fn make_embedded_runge_kutta<
+    SYS: OdeSystem,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS> + 'static,
+>(
+    create_ivp: IvpGen,
     h_start: f64,
+    tableau: EmbeddedTableau,
     tolerance: f64,
-) -> EmbeddedExplicitRungeKuttaMethod<FT> {
-    make_embedded_explicit_runge_kutta_with_tableau(
-        create_ivp,
+) -> EmbeddedExplicitRungeKuttaMethod<SYS> {
+    EmbeddedExplicitRungeKuttaMethod::new(
+        tableau,
         h_start,
-        Tableau::new(
-            vec![0.0, 1.0], // cs
-            vec![0.5, 0.5], // bs
-            vec![vec![], vec![1.0]],
-        ),
-        Tableau::new(
-            vec![0.0, 1.0], // cs
-            vec![1.0, 0.0], // bs
-            vec![vec![], vec![1.0]],
-        ),
-        1,
-        tolerance,
+        Box::new(create_ivp),
+        ErrorControl::new(tolerance, tolerance).with_norm(ErrorNorm::Max),
     )
 }
 
-/// DOPRI5 implementation.
-pub fn make_dopri5<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    create_ivp: fn() -> InitialValueSystemProblem<FT>,
+/// Embedded Heun/Euler pair of order 2(1).
+pub fn make_embedded_rk_1st_order<
+    SYS: OdeSystem,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS> + 'static,
+>(
+    create_ivp: IvpGen,
     h_start: f64,
     tolerance: f64,
-) -> EmbeddedExplicitRungeKuttaMethod<FT> {
-    let cs = vec![
-        vec![],
-        vec![0.2],
-        vec![3.0 / 40.0, 9.0 / 40.0],
-        vec![44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
-        vec![
-            19372.0 / 6561.0,
-            -25360.0 / 2187.0,
-            64448.0 / 6561.0,
-            -212.0 / 729.0,
-        ],
-        vec![
-            9017.0 / 3168.0,
-            -355.0 / 33.0,
-            46732.0 / 5247.0,
-            49.0 / 176.0,
-            -5103.0 / 18656.0,
-        ],
-        vec![
-            35.0 / 384.0,
-            0.0,
-            500.0 / 1113.0,
-            125.0 / 192.0,
-            -2187.0 / 6784.0,
-            11.0 / 84.0,
-        ],
-    ];
-
-    make_embedded_explicit_runge_kutta_with_tableau(
+) -> EmbeddedExplicitRungeKuttaMethod<SYS> {
+    make_embedded_runge_kutta(
         create_ivp,
         h_start,
-        Tableau::new(
-            vec![0.0, 0.2, 0.3, 0.8, 8.0 / 9.0, 1.0, 1.0], // cs
-            vec![
-                35.0 / 384.0,
-                0.0,
-                500.0 / 1113.0,
-                125.0 / 192.0,
-                -2187.0 / 6784.0,
-                11.0 / 84.0,
-                0.0,
-            ], // bs
-            cs.clone(),
-        ),
-        Tableau::new(
-            vec![0.0, 0.2, 0.3, 0.8, 8.0 / 9.0, 1.0, 1.0], // cs
-            vec![
-                5179.0 / 57600.0,
-                0.0,
-                7571.0 / 16695.0,
-                393.0 / 640.0,
-                -92097.0 / 339200.0,
-                187.0 / 2100.0,
-                1.0 / 40.0,
-            ], // bs
-            cs,
-        ),
-        4,
+        EmbeddedTableau::heun_euler(),
         tolerance,
     )
 }
+
+/// DOPRI5 implementation.
+/// The last stage of a step is the first of the next one, so every try after the first costs 6 evaluations.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::InitialValueSystemProblem;
+/// use ngdl_rust::embedded_rk::make_dopri5;
+///
+/// let create_problem = || {
+///     let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+///     InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+/// };
+///
+/// let solution = make_dopri5(create_problem, 0.1, 1e-8).interval(1.0, 0).unwrap();
+///
+/// let stats = solution.stats();
+/// let tries = stats.accepted_steps + stats.rejected_steps;
+/// assert_eq!(stats.rhs_evaluations, 7 + 6 * (tries - 1));
+/// ```
+pub fn make_dopri5<SYS: OdeSystem, IvpGen: Fn() -> InitialValueSystemProblem<SYS> + 'static>(
+    create_ivp: IvpGen,
+    h_start: f64,
+    tolerance: f64,
+) -> EmbeddedExplicitRungeKuttaMethod<SYS> {
+    make_embedded_runge_kutta(create_ivp, h_start, EmbeddedTableau::dopri5(), tolerance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::SampleableFunction;
+
+    /// Heun/Euler with the acceptance test and step size rule of the original implementation,
+    /// err = max |y_i - ŷ_i| / (1 + |y_i|) <= tol, the last step shortened to end at t_target.
+    fn legacy_accepted_steps(h_start: f64, tolerance: f64, t_target: f64) -> usize {
+        let (mut t, mut y, mut h) = (0.0, 1.0, h_start);
+        let mut accepted = 0;
+        while t < t_target {
+            let h_try = h.min(t_target - t);
+            let k1 = -y;
+            let k2 = -(y + h_try * k1);
+            let err = abs!(h_try / 2.0 * (k2 - k1)) / (1.0 + abs!(y));
+            h = 2.0f64.min(0.5f64.max(0.9 * (tolerance / err).powf(0.5))) * h_try;
+            if err <= tolerance {
+                y += h_try / 2.0 * (k1 + k2);
+                t = if h_try == t_target - t {
+                    t_target
+                } else {
+                    t + h_try
+                };
+                accepted += 1;
+            }
+        }
+        accepted
+    }
+
+    #[test]
+    fn test_legacy_tolerance() {
+        // y decreases, so max(|y|, |ŷ|) = |y| as in the original test
+        let create_problem = || {
+            let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+            InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+        };
+        for tolerance in [1e-2, 1e-4, 1e-6] {
+            let solution = make_embedded_rk_1st_order(create_problem, 0.1, tolerance)
+                .interval(5.0, 0)
+                .unwrap();
+            assert_eq!(
+                solution.stats().accepted_steps,
+                legacy_accepted_steps(0.1, tolerance, 5.0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_dense_output_reuses_stages() {
+        let create_problem = || {
+            let rhs = |t: f64, _y: &[f64], dydt: &mut [f64]| dydt[0] = t.cos();
+            InitialValueSystemProblem::new(0.0, vec![0.0], rhs)
+        };
+        let solution = make_dopri5(create_problem, 0.1, 1e-8)
+            .interval(2.0, 0)
+            .unwrap();
+        let dense = make_dopri5(create_problem, 0.1, 1e-8)
+            .dense_interval(2.0)
+            .unwrap();
+
+        assert_eq!(dense.stats(), solution.stats());
+        for t in [0.05, 0.77, 1.234, 1.99] {
+            assert!(abs!(dense.value_at(t)[0] - t.sin()) < 1e-7);
+        }
+    }
+
+    #[test]
+    fn test_output_times_outside_of_the_run() {
+        let create_problem = || {
+            let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+            InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+        };
+        let mut method = make_dopri5(create_problem, 0.1, 1e-6);
+
+        assert_eq!(
+            method.interval_at(&OutputTimes::At(vec![-1.0, 1.0])),
+            Err(SolverError::InvalidOutputTime { t: 1.0 })
+        );
+        let dense = method.dense_interval(1.0).unwrap();
+        assert_eq!(
+            dense.try_value_at(1.5),
+            Err(SolverError::InvalidOutputTime { t: 1.5 })
+        );
+        let solution = method
+            .interval_at(&OutputTimes::At(vec![1.0, 0.0, 0.5]))
+            .unwrap();
+        assert_eq!(solution.times(), &[0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_runs_are_independent() {
+        let create_problem = || {
+            let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+            InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+        };
+        let mut method = make_dopri5(create_problem, 0.01, 1e-6);
+
+        let first = method.interval(3.0, 0).unwrap();
+        method.interval(5.0, 0).unwrap();
+        // Starts from h_start again, not from the last adapted step size
+        assert_eq!(method.interval(3.0, 0).unwrap(), first);
+        assert_eq!(first.stats().h_min, Some(0.01));
+    }
+
+    #[test]
+    fn test_empty_interval() {
+        let create_problem = || {
+            let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+            InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+        };
+        let solution = make_dopri5(create_problem, 0.1, 1e-6)
+            .with_automatic_initial_step()
+            .interval(0.0, 0)
+            .unwrap();
+
+        assert_eq!(solution.times(), &[0.0]);
+        assert_eq!(solution.final_state(), Some(&[1.0][..]));
+        assert_eq!(*solution.stats(), SolverStats::default());
+    }
+
+    #[test]
+    fn test_empty_state() {
+        let create_problem = || {
+            let rhs = |_t: f64, _y: &[f64], _dydt: &mut [f64]| {};
+            InitialValueSystemProblem::new(0.0, Vec::new(), rhs)
+        };
+        let mut method = make_dopri5(create_problem, 0.1, 1e-6);
+
+        let expected = Err(SolverError::DimensionMismatch {
+            expected: 1,
+            found: 0,
+        });
+        assert_eq!(method.interval(1.0, 0).map(|_| ()), expected);
+        assert_eq!(
+            method.interval_at(&OutputTimes::At(vec![0.5])).map(|_| ()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_tolerance_dimension() {
+        let create_problem = || {
+            let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+            InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+        };
+        let result = make_dopri5(create_problem, 0.1, 1e-6)
+            .with_error_control(ErrorControl::new(vec![1e-6, 1e-6], 1e-6))
+            .interval(1.0, 0);
+
+        assert!(matches!(
+            result,
+            Err(SolverError::DimensionMismatch {
+                expected: 1,
+                found: 2
+            })
+        ));
+    }
+}
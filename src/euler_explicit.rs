@@ -1,10 +1,15 @@
 use crate::definitions::{
-    ComposeSampleableFunction, Function, Function2D, FunctionND, InitialValueProblem,
-    InitialValueSystemProblem, ODEMethod, Point2D, PointwiseAdd, SampleableFunction, ScalarMul,
-    SubSampleableFunction,
+    Function2D, InitialValueProblem, InitialValueSystemProblem, ODEMethod, OdeSystem, Point2D,
+    PointwiseSub, SampleableFunction, Scalar, SolverError, StateVector,
 };
-use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
+use crate::generalized_explicit_one_step_method::{
+    OneStepMethod, OneStepMethodStep, StepWorkspace,
+};
+use crate::method_info::MethodInfo;
+use crate::solution::Solution;
+use crate::stats::SolverStats;
 use crate::util::euclidean_norm;
+use num::complex::Complex64;
 use rayon::prelude::*;
 
 /// Simple implementation of the explicit euler method.
@@ -24,10 +29,14 @@ use rayon::prelude::*;
 /// let ivp: InitialValueProblem<Function2D> = InitialValueProblem::new(0.0, 1.0, |(_, x)| x*x);
 /// dbg!(explicit_euler(ivp, 0.001, 1.0));
 /// ```
-pub fn explicit_euler(ivp: InitialValueProblem<Function2D>, h: f64, t_target: f64) -> f64 {
-    let ps = explicit_euler_interval(ivp, h, t_target, 0);
-    let p = ps.last().unwrap();
-    p.y
+pub fn explicit_euler(
+    ivp: InitialValueProblem<Function2D>,
+    h: f64,
+    t_target: f64,
+) -> Result<f64, SolverError> {
+    let ps = explicit_euler_interval(ivp, h, t_target, 0)?;
+    // There always is at least the start value
+    Ok(ps.last().unwrap().y)
 }
 
 /// Simple implementation of the explicit euler method.
@@ -47,12 +56,9 @@ pub fn explicit_euler_interval(
     h: f64,
     t_target: f64,
     skip_n: isize,
-) -> Vec<Point2D> {
+) -> Result<Vec<Point2D>, SolverError> {
     let problem = ivp.to_system_problem();
-    explicit_euler_system_interval(&problem, h, t_target, skip_n)
-        .iter()
-        .map(|vec_x| vec_x[0])
-        .collect()
+    Ok(explicit_euler_system_interval(&problem, h, t_target, skip_n)?.component_points(0))
 }
 
 /// Implementation of the explicit euler method for multiple dimensions.
@@ -78,18 +84,19 @@ pub fn explicit_euler_interval(
 ///
 /// dbg!(explicit_euler_system(&problem, h, t_target));
 /// ```
-pub fn explicit_euler_system<FT: SampleableFunction<(f64, Vec<f64>), f64> + Clone>(
-    ivp: &InitialValueSystemProblem<FT>,
+pub fn explicit_euler_system<SYS: OdeSystem + Clone>(
+    ivp: &InitialValueSystemProblem<SYS>,
     h: f64,
     t_target: f64,
-) -> Vec<f64> {
-    let results = explicit_euler_system_interval(ivp, h, t_target, 0);
-    results.last().unwrap().iter().map(|p| p.y).collect()
+) -> Result<Vec<f64>, SolverError> {
+    let results = explicit_euler_system_interval(ivp, h, t_target, 0)?;
+    // There always is at least the start value
+    Ok(results.final_state().unwrap().to_vec())
 }
 
 /// Implementation of the explicit euler method for multiple dimensions.
 /// Lands on target even if h does not match.
-/// The function returns the intermediate values as well as the final value in the form of a point vector
+/// The function returns the intermediate values as well as the final value in the form of a solution
 ///
 /// # Arguments
 ///
@@ -98,12 +105,12 @@ pub fn explicit_euler_system<FT: SampleableFunction<(f64, Vec<f64>), f64> + Clon
 /// * `t_target` - Target time we want to get the value for.
 /// * `skip_n` - If > 0 only returns ever n-th value to reduce memory footprint while retaining smaller h
 ///
-pub fn explicit_euler_system_interval<FT: SampleableFunction<(f64, Vec<f64>), f64> + Clone>(
-    ivp: &InitialValueSystemProblem<FT>,
+pub fn explicit_euler_system_interval<SYS: OdeSystem + Clone>(
+    ivp: &InitialValueSystemProblem<SYS>,
     h: f64,
     t_target: f64,
     skip_n: isize,
-) -> Vec<Vec<Point2D>> {
+) -> Result<Solution, SolverError> {
     let method = make_explicit_euler_method_system((*ivp).clone(), h);
     method.interval(t_target, skip_n)
 }
@@ -113,7 +120,7 @@ pub fn explicit_euler_test_run(
     ivp: InitialValueProblem<Function2D>,
     h: &[f64],
     t_target: f64,
-) -> Vec<f64> {
+) -> Result<Vec<f64>, SolverError> {
     h.par_iter()
         .map(|h| explicit_euler(ivp, *h, t_target))
         .collect()
@@ -125,7 +132,7 @@ pub fn explicit_euler_interval_test_run(
     h: &[f64],
     t_target: f64,
     skip_n: isize,
-) -> Vec<Vec<Point2D>> {
+) -> Result<Vec<Vec<Point2D>>, SolverError> {
     h.par_iter()
         .map(|h| explicit_euler_interval(ivp, *h, t_target, skip_n))
         .collect()
@@ -133,42 +140,90 @@ pub fn explicit_euler_interval_test_run(
 
 /// Get the residual as defined for the error bounds of the euler method.
 /// This returns a function that can be sampled to get the residual at a certain t.
-pub fn get_residual_function(
-    create_problem: fn() -> InitialValueSystemProblem<Function<(f64, Vec<f64>)>>,
+pub fn get_residual_function<SYS: OdeSystem, IvpGen: Fn() -> InitialValueSystemProblem<SYS>>(
+    create_problem: IvpGen,
     h: f64,
 ) -> impl SampleableFunction<f64, f64> {
     // First a priori error
-    let euler_method = make_explicit_euler_method_system(create_problem(), h);
-    let slope_polygonal_spline =
-        make_explicit_euler_method_system(create_problem(), h).get_derivative();
-    // f (spline values)
-    let f: FunctionND<Vec<f64>, Vec<f64>> = |vec_x| vec![-vec_x[0] + vec_x[1], vec_x[0] - vec_x[1]];
-    let f_tmp = ComposeSampleableFunction::new(euler_method, f);
-    let under_norm = SubSampleableFunction::new(slope_polygonal_spline, f_tmp);
-    let f: Function<Vec<f64>> = |v| euclidean_norm(v);
+    // Both are sampled many times, so only integrate once
+    let euler_method = make_explicit_euler_method_system(create_problem(), h).cached();
+    let slope_polygonal_spline = make_explicit_euler_method_system(create_problem(), h)
+        .cached()
+        .get_derivative();
+    let system = create_problem().dfs;
     // Residual
-    ComposeSampleableFunction::new(under_norm, f)
+    move |t: f64| {
+        // f (spline values)
+        let f = system.rhs_vec(t, &euler_method.value_at(t));
+        euclidean_norm(slope_polygonal_spline.value_at(t).pointwise_sub(f))
+    }
 }
 
 /// Simple implementation of the step taken during the explicit euler function.
 /// Done for arbitrary dimensions.
 pub struct ExplicitEulerStep;
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> OneStepMethodStep<FT> for ExplicitEulerStep {
-    fn step(&self, dfs: &[FT], t: f64, last_values: &[f64], h: f64) -> Vec<f64> {
-        let last_values_owned: Vec<f64> = last_values.to_vec();
-        dfs.iter()
-            .map(|df| df.value_at((t, last_values_owned.clone())))
-            .collect::<Vec<f64>>()
-            .scalar_mul(h)
-            .pointwise_add(last_values_owned)
+impl<SYS: OdeSystem<S::Scalar>, S: StateVector> OneStepMethodStep<SYS, S> for ExplicitEulerStep {
+    fn step(
+        &self,
+        system: &SYS,
+        t: f64,
+        last_values: &S,
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> Result<S, SolverError> {
+        let mut next_values = last_values.clone();
+        self.step_in_place(
+            system,
+            t,
+            &mut next_values,
+            h,
+            &mut StepWorkspace::default(),
+            stats,
+        )?;
+        Ok(next_values)
+    }
+
+    fn step_in_place(
+        &self,
+        system: &SYS,
+        t: f64,
+        values: &mut S,
+        h: f64,
+        workspace: &mut StepWorkspace<S>,
+        stats: &mut SolverStats,
+    ) -> Result<(), SolverError> {
+        stats.rhs_evaluations += 1;
+        let slope = &mut workspace.buffers(1, values)[0];
+        system.rhs(t, values.as_slice(), slope.as_mut_slice());
+
+        values.axpy(S::Scalar::from_f64(h), slope);
+        Ok(())
+    }
+}
+
+impl MethodInfo for ExplicitEulerStep {
+    fn name(&self) -> &str {
+        "Explicit Euler"
+    }
+
+    fn order(&self) -> usize {
+        1
+    }
+
+    fn stages(&self) -> usize {
+        1
+    }
+
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        Some(1.0 + z)
     }
 }
 
 /// Makes a system of ODEs into a sampleable function using the explicit euler method.
-pub fn make_explicit_euler_method_system<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    ivp: InitialValueSystemProblem<FT>,
+pub fn make_explicit_euler_method_system<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
-) -> OneStepMethod<FT, ExplicitEulerStep> {
+) -> OneStepMethod<SYS, ExplicitEulerStep, S> {
     OneStepMethod::new(ExplicitEulerStep, ivp, h)
 }
@@ -0,0 +1,414 @@
+use crate::definitions::{OdeSystem, SampleableFunction, Scalar, StateVector};
+use crate::dense_output::DenseSolution;
+use crate::solution::Solution;
+use crate::stats::SolverStats;
+
+/// Iterations after which the root refinement gives up and takes the current estimate.
+const MAX_ITERATIONS: usize = 100;
+
+/// The function g(t, y) whose zeros are the events.
+pub type EventFunction<N = f64> = Box<dyn Fn(f64, &[N]) -> f64>;
+/// A solution together with all located event occurrences.
+pub type SolutionWithEvents<N = f64> = (Solution<N>, Vec<EventOccurrence<N>>);
+
+/// Which zero crossings of an event function are reported.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction {
+    /// g goes from negative to positive
+    Rising,
+    /// g goes from positive to negative
+    Falling,
+    /// Every sign change
+    Both,
+}
+
+/// An event that occurs when g(t, y) crosses zero.
+/// Non terminal and reacting to both directions by default.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::InitialValueSystemProblem;
+/// use ngdl_rust::events::{Direction, Event};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// // Ball thrown upwards, y = (height, velocity)
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+///     dydt[0] = y[1];
+///     dydt[1] = -9.81;
+/// };
+/// let problem = InitialValueSystemProblem::new(0.0, vec![0.0, 10.0], rhs);
+/// let hits_ground = Event::new(|_t, y: &[f64]| y[0])
+///     .direction(Direction::Falling)
+///     .terminal();
+///
+/// let (solution, events) = make_classic_runge_kutta(problem, 0.1)
+///     .interval_with_events(10.0, 0, &[hits_ground])
+///     .unwrap();
+///
+/// assert_eq!(events.len(), 1);
+/// assert!((events[0].t - 20.0 / 9.81).abs() < 1e-10);
+/// assert_eq!(solution.final_time(), Some(events[0].t));
+/// ```
+pub struct Event<N: Scalar = f64> {
+    g: EventFunction<N>,
+    direction: Direction,
+    is_terminal: bool,
+}
+
+impl<N: Scalar> Event<N> {
+    /// Event at the zeros of g.
+    pub fn new<G: Fn(f64, &[N]) -> f64 + 'static>(g: G) -> Self {
+        Event {
+            g: Box::new(g),
+            direction: Direction::Both,
+            is_terminal: false,
+        }
+    }
+
+    /// Only report crossings in this direction.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Stop the integration at the first occurrence.
+    pub fn terminal(mut self) -> Self {
+        self.is_terminal = true;
+        self
+    }
+
+    /// `g_last` and `g` are the values at the start and the end of a step,
+    /// `backwards` if that step went back in time.
+    fn triggers(&self, g_last: f64, g: f64, backwards: bool) -> bool {
+        let rising = g_last < 0.0 && g >= 0.0;
+        let falling = g_last > 0.0 && g <= 0.0;
+        // The direction refers to increasing time
+        let (rising, falling) = if backwards {
+            (falling, rising)
+        } else {
+            (rising, falling)
+        };
+        match self.direction {
+            Direction::Rising => rising,
+            Direction::Falling => falling,
+            Direction::Both => rising || falling,
+        }
+    }
+}
+
+/// A located zero crossing of an event function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventOccurrence<N: Scalar = f64> {
+    /// Index of the event in the slice passed to the solver
+    pub event: usize,
+    /// Time of the crossing
+    pub t: f64,
+    /// Interpolated state at the crossing
+    pub state: Vec<N>,
+}
+
+/// Watches the steps of a solver for zero crossings of the events.
+/// Only steps with a sign change are interpolated, by default with a cubic Hermite polynomial,
+/// the crossing is then refined with the Illinois variant of regula falsi.
+pub(crate) struct EventDetector<'a, N: Scalar> {
+    events: &'a [Event<N>],
+    t_last: f64,
+    values_last: Vec<N>,
+    gs_last: Vec<f64>,
+    occurrences: Vec<EventOccurrence<N>>,
+}
+
+impl<'a, N: Scalar> EventDetector<'a, N> {
+    /// Starts watching at (t, values), None if there is nothing to watch.
+    pub(crate) fn watch(events: &'a [Event<N>], t: f64, values: &[N]) -> Option<Self> {
+        if events.is_empty() {
+            return None;
+        }
+        Some(EventDetector {
+            events,
+            t_last: t,
+            values_last: values.to_vec(),
+            gs_last: events.iter().map(|event| (event.g)(t, values)).collect(),
+            occurrences: Vec::new(),
+        })
+    }
+
+    /// Looks for crossings in the step ending in (t, values).
+    /// Returns the first terminal occurrence, all crossings before it are recorded.
+    /// The slopes of the Hermite interpolation cost two evaluations of f, counted in `stats`.
+    pub(crate) fn check_step<SYS: OdeSystem<N>>(
+        &mut self,
+        system: &SYS,
+        t: f64,
+        values: &[N],
+        stats: &mut SolverStats,
+    ) -> Option<EventOccurrence<N>> {
+        self.check_interpolated_step(t, values, |t_last, values_last| {
+            stats.rhs_evaluations += 2;
+            let mut step = DenseSolution::new(t_last, values_last, 3);
+            step.push_hermite(
+                t,
+                values,
+                &system.rhs_vec(t_last, values_last),
+                &system.rhs_vec(t, values),
+            );
+            step
+        })
+    }
+
+    /// Like `check_step`, but `interpolate` builds the interpolant of the step from its start (t_last, values_last),
+    /// e.g. from the stages of a Runge-Kutta method. It is only called for steps with a sign change.
+    pub(crate) fn check_interpolated_step(
+        &mut self,
+        t: f64,
+        values: &[N],
+        interpolate: impl FnOnce(f64, &[N]) -> DenseSolution<N>,
+    ) -> Option<EventOccurrence<N>> {
+        let gs: Vec<f64> = self
+            .events
+            .iter()
+            .map(|event| (event.g)(t, values))
+            .collect();
+
+        let backwards = t < self.t_last;
+        let mut found: Vec<EventOccurrence<N>> = Vec::new();
+        if self
+            .events
+            .iter()
+            .enumerate()
+            .any(|(idx, event)| event.triggers(self.gs_last[idx], gs[idx], backwards))
+        {
+            let step = interpolate(self.t_last, &self.values_last);
+
+            for (idx, event) in self.events.iter().enumerate() {
+                if event.triggers(self.gs_last[idx], gs[idx], backwards) {
+                    let t_event = self.locate(event, &step, gs[idx], t, idx);
+                    found.push(EventOccurrence {
+                        event: idx,
+                        t: t_event,
+                        state: step.value_at(t_event),
+                    });
+                }
+            }
+            // In the order they are reached, NaN times last
+            let direction = if backwards { -1.0 } else { 1.0 };
+            let t_last = self.t_last;
+            found.sort_by(|e1, e2| {
+                ((e1.t - t_last) * direction).total_cmp(&((e2.t - t_last) * direction))
+            });
+        }
+
+        self.t_last = t;
+        self.values_last = values.to_vec();
+        self.gs_last = gs;
+
+        for occurrence in found {
+            let is_terminal = self.events[occurrence.event].is_terminal;
+            self.occurrences.push(occurrence.clone());
+            if is_terminal {
+                return Some(occurrence);
+            }
+        }
+        None
+    }
+
+    fn locate(&self, event: &Event<N>, step: &DenseSolution<N>, g: f64, t: f64, idx: usize) -> f64 {
+        let g_at = |t: f64| (event.g)(t, &step.value_at(t));
+        let tolerance = 4.0 * f64::EPSILON * t.abs().max(1.0);
+
+        // Illinois: like regula falsi, but the stale end of the bracket gets its value halved
+        let (mut t_a, mut g_a) = (self.t_last, self.gs_last[idx]);
+        let (mut t_b, mut g_b) = (t, g);
+        for _ in 0..MAX_ITERATIONS {
+            if (t_b - t_a).abs() <= tolerance {
+                break;
+            }
+            let t_c = (t_a * g_b - t_b * g_a) / (g_b - g_a);
+            let t_c = t_c.max(self.t_last.min(t)).min(self.t_last.max(t));
+            let g_c = g_at(t_c);
+            if g_c == 0.0 || (t_c - t_b).abs() <= tolerance {
+                return t_c;
+            }
+            if g_c * g_b < 0.0 {
+                t_a = t_b;
+                g_a = g_b;
+            } else {
+                g_a /= 2.0;
+            }
+            t_b = t_c;
+            g_b = g_c;
+        }
+        t_b
+    }
+
+    /// Like `check_step`, but for states of the solver.
+    /// Returns the time and state of the first terminal occurrence.
+    pub(crate) fn check_state<SYS: OdeSystem<N>, S: StateVector<Scalar = N>>(
+        &mut self,
+        system: &SYS,
+        t: f64,
+        values: &S,
+        stats: &mut SolverStats,
+    ) -> Option<(f64, S)> {
+        self.check_step(system, t, values.as_slice(), stats)
+            .map(|occurrence| {
+                let mut state = values.zeros_like();
+                state.as_mut_slice().copy_from_slice(&occurrence.state);
+                (occurrence.t, state)
+            })
+    }
+
+    /// Hands out all occurrences found so far.
+    pub(crate) fn take_occurrences(&mut self) -> Vec<EventOccurrence<N>> {
+        std::mem::take(&mut self.occurrences)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::{InitialValueSystemProblem, ODEMethod};
+    use crate::embedded_rk::make_dopri5;
+    use crate::explicit_runge_kutta::make_classic_runge_kutta;
+
+    #[test]
+    fn test_terminal_events_backwards() {
+        // y = t, one step back from t = 1 to t = 0 passes y = 0.8 before y = 0.3
+        let system = |_t: f64, _y: &[f64], dydt: &mut [f64]| dydt[0] = 1.0;
+        let events = [
+            Event::new(|_t, y: &[f64]| y[0] - 0.3).terminal(),
+            Event::new(|_t, y: &[f64]| y[0] - 0.8).terminal(),
+        ];
+        let mut detector = EventDetector::watch(&events, 1.0, &[1.0]).unwrap();
+        let mut stats = SolverStats::default();
+
+        let occurrence = detector
+            .check_step(&system, 0.0, &[0.0], &mut stats)
+            .unwrap();
+        assert_eq!(occurrence.event, 1);
+        assert!((occurrence.t - 0.8).abs() < 1e-12);
+        assert_eq!(detector.take_occurrences().len(), 1);
+        // The slopes at both ends of the step
+        assert_eq!(stats.rhs_evaluations, 2);
+    }
+
+    /// Harmonic oscillator with y_0 = sin t.
+    fn oscillator() -> InitialValueSystemProblem<impl OdeSystem + Clone> {
+        let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+            dydt[0] = y[1];
+            dydt[1] = -y[0];
+        };
+        InitialValueSystemProblem::new(0.0, vec![0.0, 1.0], rhs)
+    }
+
+    #[test]
+    fn test_event_time_accuracy() {
+        // y = e^t reaches 2 at ln 2, far from the grid points
+        let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = y[0];
+        let problem = InitialValueSystemProblem::new(0.0, vec![1.0], rhs);
+        let events = [Event::new(|_t, y: &[f64]| y[0] - 2.0)];
+
+        let (solution, occurrences) = make_classic_runge_kutta(problem, 0.1)
+            .interval_with_events(1.0, 0, &events)
+            .unwrap();
+
+        assert_eq!(occurrences.len(), 1);
+        assert!((occurrences[0].t - 2.0f64.ln()).abs() < 1e-6);
+        assert!((occurrences[0].state[0] - 2.0).abs() < 1e-6);
+        // Not terminal, so the integration goes on
+        assert_eq!(solution.final_time(), Some(1.0));
+    }
+
+    #[test]
+    fn test_directions() {
+        let sin = |_t: f64, y: &[f64]| y[0];
+        let events = [
+            Event::new(sin).direction(Direction::Rising),
+            Event::new(sin).direction(Direction::Falling),
+            Event::new(sin),
+        ];
+
+        let (_, occurrences) = make_classic_runge_kutta(oscillator(), 0.01)
+            .interval_with_events(7.0, 0, &events)
+            .unwrap();
+
+        let times = |idx: usize| -> Vec<f64> {
+            occurrences
+                .iter()
+                .filter(|occurrence| occurrence.event == idx)
+                .map(|occurrence| occurrence.t)
+                .collect()
+        };
+        let pi = std::f64::consts::PI;
+        assert!(matches!(times(0)[..], [t] if (t - 2.0 * pi).abs() < 1e-8));
+        assert!(matches!(times(1)[..], [t] if (t - pi).abs() < 1e-8));
+        assert_eq!(times(2).len(), 2);
+        // In the order of the integration
+        assert!(occurrences.windows(2).all(|o| o[0].t <= o[1].t));
+    }
+
+    #[test]
+    fn test_directions_backwards() {
+        // Going back from 0, sin t falls through 0 at -π when seen with increasing time
+        let events = [
+            Event::new(|_t, y: &[f64]| y[0]).direction(Direction::Rising),
+            Event::new(|_t, y: &[f64]| y[0])
+                .direction(Direction::Falling)
+                .terminal(),
+        ];
+
+        let (solution, occurrences) = make_classic_runge_kutta(oscillator(), 0.01)
+            .interval_with_events(-7.0, 0, &events)
+            .unwrap();
+
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].event, 1);
+        assert!((occurrences[0].t + std::f64::consts::PI).abs() < 1e-8);
+        assert_eq!(solution.final_time(), Some(occurrences[0].t));
+    }
+
+    #[test]
+    fn test_counts_evaluations() {
+        // sin t crosses zero at pi and 2 pi
+        let events = [Event::new(|_t, y: &[f64]| y[0])];
+
+        let method = make_classic_runge_kutta(oscillator(), 0.1);
+        let plain = method.interval(7.0, 0).unwrap();
+        let (solution, occurrences) = method.interval_with_events(7.0, 0, &events).unwrap();
+        assert_eq!(occurrences.len(), 2);
+        // Two slopes for each step with a crossing
+        assert_eq!(
+            solution.stats().rhs_evaluations,
+            plain.stats().rhs_evaluations + 4
+        );
+
+        // DOPRI5 interpolates with its stages, FSAL gives the slope at the end for free
+        let plain = make_dopri5(oscillator, 0.1, 1e-8).interval(7.0, 0).unwrap();
+        let (solution, occurrences) = make_dopri5(oscillator, 0.1, 1e-8)
+            .interval_with_events(7.0, 0, &events)
+            .unwrap();
+        assert_eq!(occurrences.len(), 2);
+        assert_eq!(solution.stats(), plain.stats());
+    }
+
+    #[test]
+    fn test_dense_output_interpolant() {
+        let create_problem = || {
+            let rhs = |t: f64, _y: &[f64], dydt: &mut [f64]| dydt[0] = t.cos();
+            InitialValueSystemProblem::new(0.0, vec![0.0], rhs)
+        };
+        let events = [Event::new(|_t, y: &[f64]| y[0] - 0.5)];
+
+        let (_, occurrences) = make_dopri5(create_problem, 0.5, 1e-4)
+            .interval_with_events(2.0, 0, &events)
+            .unwrap();
+        let dense = make_dopri5(create_problem, 0.5, 1e-4)
+            .dense_interval(2.0)
+            .unwrap();
+
+        // Located on the 4th order dense output, not on a Hermite polynomial
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].state, dense.value_at(occurrences[0].t));
+        assert!((occurrences[0].t - std::f64::consts::FRAC_PI_6).abs() < 1e-5);
+    }
+}
@@ -1,69 +1,327 @@
-use crate::definitions::{InitialValueSystemProblem, PointwiseAdd, SampleableFunction, ScalarMul};
-use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
-use crate::util::make_zero_vec;
+use crate::abs;
+use crate::definitions::{InitialValueSystemProblem, OdeSystem, Scalar, SolverError, StateVector};
+use crate::generalized_explicit_one_step_method::{
+    OneStepMethod, OneStepMethodStep, StepWorkspace,
+};
+use crate::method_info::MethodInfo;
+use crate::stats::SolverStats;
 use derive_new::*;
+use num::complex::Complex64;
+use num::Zero;
 use std::marker::PhantomData;
 
 /// This is a tableau for a Runge-Kutta method.
 /// I would prefer to enforce same size for all of these, but the required Rust feature (const generics) has not yet stabilized.
+/// The weights use the scalar type of the state, the nodes are times and therefore always f64.
 #[derive(Clone, Debug, new)]
-pub struct Tableau {
+pub struct Tableau<N: Scalar = f64> {
     cs: Vec<f64>,
-    bs: Vec<f64>,
-    coeffs: Vec<Vec<f64>>,
+    bs: Vec<N>,
+    coeffs: Vec<Vec<N>>,
+    #[new(value = "String::from(\"Explicit Runge-Kutta\")")]
+    name: String,
 }
 
-/// Implementation for a RK method with only explicit components.
-#[derive(Clone, Debug, new)]
-pub struct ExplicitRungeKuttaMethod<FT: SampleableFunction<(f64, Vec<f64>), f64>> {
-    _t: PhantomData<FT>,
-    tableau: Tableau,
-}
+impl<N: Scalar> Tableau<N> {
+    /// Builds a tableau from weights given as (numerator, denominator).
+    /// The divisions are done in N, so e.g. 1/3 is exact up to the precision of N and not only of f64.
+    pub fn from_ratios(cs: Vec<f64>, bs: &[(f64, f64)], coeffs: &[&[(f64, f64)]]) -> Self {
+        let to_scalars =
+            |ratios: &[(f64, f64)]| ratios.iter().map(|(n, d)| N::from_ratio(*n, *d)).collect();
+        Tableau::new(
+            cs,
+            to_scalars(bs),
+            coeffs.iter().map(|row| to_scalars(row)).collect(),
+        )
+    }
+
+    /// Names the method, e.g. for captions.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> ExplicitRungeKuttaMethod<FT> {
-    fn get_ks(&self, dfs: &[FT], t: f64, last_values: &[f64], h: f64) -> Vec<Vec<f64>> {
-        let mut ks: Vec<Vec<f64>> = Vec::with_capacity(self.tableau.cs.len());
+    /// Classic 4-th order tableau.
+    pub fn classic() -> Self {
+        Tableau::from_ratios(
+            vec![0.0, 0.5, 0.5, 1.0],                          // cs
+            &[(1.0, 6.0), (1.0, 3.0), (1.0, 3.0), (1.0, 6.0)], // bs
+            &[
+                &[],
+                &[(1.0, 2.0)],
+                &[(0.0, 1.0), (1.0, 2.0)],
+                &[(0.0, 1.0), (0.0, 1.0), (1.0, 1.0)],
+            ],
+        )
+        .with_name("Classic Runge-Kutta")
+    }
 
-        for (idx, c) in self.tableau.cs.iter().enumerate() {
+    /// Tableau of England's 4-th order method.
+    pub fn england() -> Self {
+        Tableau::from_ratios(
+            vec![0.0, 0.5, 0.5, 1.0],                          // cs
+            &[(1.0, 6.0), (0.0, 1.0), (2.0, 3.0), (1.0, 6.0)], // bs
+            &[
+                &[],
+                &[(1.0, 2.0)],
+                &[(1.0, 4.0), (1.0, 4.0)],
+                &[(0.0, 1.0), (-1.0, 1.0), (2.0, 1.0)],
+            ],
+        )
+        .with_name("England")
+    }
+
+    /// Tableau of the 3/8 method as given in the task, which only has order 3.
+    pub fn three_eight() -> Self {
+        Tableau::from_ratios(
+            vec![0.0, 0.5, 1.0, 1.0],                          // cs
+            &[(1.0, 6.0), (2.0, 3.0), (0.0, 1.0), (1.0, 6.0)], // bs
+            &[
+                &[],
+                &[(1.0, 2.0)],
+                &[(0.0, 1.0), (1.0, 1.0)],
+                &[(0.0, 1.0), (0.0, 1.0), (1.0, 1.0)],
+            ],
+        )
+        .with_name("3/8 rule")
+    }
+
+    /// Tableau of the 2nd order midpoint method.
+    pub fn second_order() -> Self {
+        Tableau::from_ratios(
+            vec![0.0, 0.5],            // cs
+            &[(0.0, 1.0), (1.0, 1.0)], // bs
+            &[&[], &[(1.0, 2.0)]],
+        )
+        .with_name("Midpoint")
+    }
+
+    /// Tableau of Heun's 3rd order method.
+    pub fn heun() -> Self {
+        Tableau::from_ratios(
+            vec![0.0, 1.0 / 3.0, 2.0 / 3.0],       // cs
+            &[(1.0, 4.0), (0.0, 1.0), (3.0, 4.0)], // bs
+            &[&[], &[(1.0, 3.0)], &[(0.0, 1.0), (2.0, 3.0)]],
+        )
+        .with_name("Heun")
+    }
+
+    /// Evaluates the stages k_i of a step of size h starting in (t, last_values) into the first buffers
+    /// and uses the one after them as scratch space for the arguments of the right-hand side.
+    /// The first known_stages stages are already in the buffers and are kept, e.g. the last stage of a FSAL method.
+    /// Zero entries of the tableau are skipped, a stage without any is evaluated at last_values directly.
+    pub(crate) fn evaluate_stages_into<SYS: OdeSystem<N>, S: StateVector<Scalar = N>>(
+        &self,
+        system: &SYS,
+        t: f64,
+        last_values: &S,
+        h: f64,
+        buffers: &mut [S],
+        known_stages: usize,
+    ) {
+        let h_n = N::from_f64(h);
+        let (ks, sample_vals) = buffers.split_at_mut(self.cs.len());
+        let sample_vals = &mut sample_vals[0];
+
+        for (idx, c) in self.cs.iter().enumerate().skip(known_stages) {
             // We currently calculate k_idx
             let t_sample = t + h * *c;
-            let mut sample_vals: Vec<f64> = make_zero_vec(dfs.len());
             // For the current row take all as that are below the diagonal
-            for (idx_inner, a) in self.tableau.coeffs[idx].iter().enumerate().take(idx) {
-                // get the values in k_idx_inner, multiply with a and sum up
-                sample_vals = sample_vals.pointwise_add(ks[idx_inner].clone().scalar_mul(*a));
+            let mut row = self.coeffs[idx]
+                .iter()
+                .enumerate()
+                .take(idx)
+                .filter(|(_, a)| !a.is_zero())
+                .peekable();
+            if row.peek().is_none() {
+                system.rhs(t_sample, last_values.as_slice(), ks[idx].as_mut_slice());
+                continue;
+            }
+
+            sample_vals
+                .as_mut_slice()
+                .copy_from_slice(last_values.as_slice());
+            for (idx_inner, a) in row {
+                // get the values in k_idx_inner, multiply with h * a and sum up
+                sample_vals.axpy(h_n * *a, &ks[idx_inner]);
             }
-            sample_vals = sample_vals
-                .scalar_mul(h)
-                .pointwise_add(last_values.iter().copied().collect());
-
-            ks.push(
-                dfs.iter()
-                    .map(|f| f.value_at((t_sample, sample_vals.clone())))
-                    .collect(),
-            );
+            system.rhs(t_sample, sample_vals.as_slice(), ks[idx].as_mut_slice());
         }
+    }
+}
+
+impl Tableau {
+    /// The weights b_i of the stages.
+    pub(crate) fn weights(&self) -> &[f64] {
+        &self.bs
+    }
+
+    /// Whether the last stage is evaluated at the result of the step ("first same as last"),
+    /// so it is the first stage of the next step.
+    pub(crate) fn is_fsal(&self) -> bool {
+        let stages = self.bs.len();
+        stages > 1
+            && self.cs[stages - 1] == 1.0
+            && self.bs[stages - 1] == 0.0
+            && self.coeffs[stages - 1]
+                .iter()
+                .take(stages - 1)
+                .eq(&self.bs[..stages - 1])
+    }
+
+    /// Checks the order conditions up to order 5 for c_i = sum_j a_ij.
+    /// Hairer, Norsett, Wanner: Solving ODEs I, section II.2
+    fn order_from_conditions(&self) -> usize {
+        let stages = self.bs.len();
+        let a = |i: usize, j: usize| self.coeffs[i].get(j).copied().unwrap_or(0.0);
+        // (A v)_i
+        let apply = |v: &[f64]| -> Vec<f64> {
+            (0..stages)
+                .map(|i| (0..i).map(|j| a(i, j) * v[j]).sum())
+                .collect()
+        };
+        let times =
+            |v: &[f64], w: &[f64]| -> Vec<f64> { v.iter().zip(w).map(|(v, w)| v * w).collect() };
+        let b_dot = |v: &[f64]| -> f64 { self.bs.iter().zip(v).map(|(b, v)| b * v).sum() };
+
+        let e = vec![1.0; stages];
+        let c = apply(&e);
+        let c2 = times(&c, &c);
+        let c3 = times(&c2, &c);
+        let ac = apply(&c);
+        let ac2 = apply(&c2);
+        let aac = apply(&ac);
+
+        let conditions: [&[(f64, f64)]; 5] = [
+            &[(b_dot(&e), 1.0)],
+            &[(b_dot(&c), 1.0 / 2.0)],
+            &[(b_dot(&c2), 1.0 / 3.0), (b_dot(&ac), 1.0 / 6.0)],
+            &[
+                (b_dot(&c3), 1.0 / 4.0),
+                (b_dot(&times(&c, &ac)), 1.0 / 8.0),
+                (b_dot(&ac2), 1.0 / 12.0),
+                (b_dot(&aac), 1.0 / 24.0),
+            ],
+            &[
+                (b_dot(&times(&c3, &c)), 1.0 / 5.0),
+                (b_dot(&times(&c2, &ac)), 1.0 / 10.0),
+                (b_dot(&times(&c, &ac2)), 1.0 / 15.0),
+                (b_dot(&times(&c, &aac)), 1.0 / 30.0),
+                (b_dot(&times(&ac, &ac)), 1.0 / 20.0),
+                (b_dot(&apply(&c3)), 1.0 / 20.0),
+                (b_dot(&apply(&times(&c, &ac))), 1.0 / 40.0),
+                (b_dot(&apply(&ac2)), 1.0 / 60.0),
+                (b_dot(&apply(&aac)), 1.0 / 120.0),
+            ],
+        ];
+        conditions
+            .iter()
+            .take_while(|conditions| {
+                conditions
+                    .iter()
+                    .all(|(value, expected)| abs!(value - expected) < 1e-10)
+            })
+            .count()
+    }
+}
 
-        ks
+impl MethodInfo for Tableau {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Derived from the order conditions, so custom tableaus report their real order (at most 5).
+    fn order(&self) -> usize {
+        self.order_from_conditions()
+    }
+
+    fn stages(&self) -> usize {
+        self.bs.len()
+    }
+
+    /// R(z) = 1 + z b^T (I - zA)^-1 1, the inverse is a forward substitution as A is strictly lower triangular.
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        let mut us: Vec<Complex64> = Vec::with_capacity(self.bs.len());
+        for row in self.coeffs.iter().take(self.bs.len()) {
+            let sum: Complex64 = row.iter().zip(&us).map(|(a, u)| u * *a).sum();
+            us.push(1.0 + z * sum);
+        }
+        let sum: Complex64 = self.bs.iter().zip(&us).map(|(b, u)| u * *b).sum();
+        Some(1.0 + z * sum)
     }
 }
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> OneStepMethodStep<FT>
-    for ExplicitRungeKuttaMethod<FT>
+/// Implementation for a RK method with only explicit components.
+#[derive(Clone, Debug, new)]
+pub struct ExplicitRungeKuttaMethod<SYS: OdeSystem<N>, N: Scalar = f64> {
+    _t: PhantomData<SYS>,
+    tableau: Tableau<N>,
+}
+
+impl<SYS: OdeSystem<S::Scalar>, S: StateVector> OneStepMethodStep<SYS, S>
+    for ExplicitRungeKuttaMethod<SYS, S::Scalar>
 {
-    fn step(&self, dfs: &[FT], t: f64, last_values: &[f64], h: f64) -> Vec<f64> {
-        let ks: Vec<Vec<f64>> = self.get_ks(dfs, t, last_values, h);
-        let change_term: Vec<f64> = self
-            .tableau
-            .bs
-            .iter()
-            .zip(ks)
-            .map(|(b, k)| k.scalar_mul(*b))
-            .fold(make_zero_vec(dfs.len()), |v1, v2| v1.pointwise_add(v2));
+    fn step(
+        &self,
+        system: &SYS,
+        t: f64,
+        last_values: &S,
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> Result<S, SolverError> {
+        let mut next_values = last_values.clone();
+        self.step_in_place(
+            system,
+            t,
+            &mut next_values,
+            h,
+            &mut StepWorkspace::default(),
+            stats,
+        )?;
+        Ok(next_values)
+    }
+
+    /// Uses one buffer per stage and one for the arguments of the right-hand side.
+    fn step_in_place(
+        &self,
+        system: &SYS,
+        t: f64,
+        values: &mut S,
+        h: f64,
+        workspace: &mut StepWorkspace<S>,
+        stats: &mut SolverStats,
+    ) -> Result<(), SolverError> {
+        let stages = self.tableau.cs.len();
+        let buffers = workspace.buffers(stages + 1, values);
+        self.tableau
+            .evaluate_stages_into(system, t, values, h, buffers, 0);
+        stats.rhs_evaluations += stages;
+        let h_n = S::Scalar::from_f64(h);
+
+        for (b, k) in self.tableau.bs.iter().zip(buffers.iter()) {
+            if !b.is_zero() {
+                values.axpy(h_n * *b, k);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<SYS: OdeSystem> MethodInfo for ExplicitRungeKuttaMethod<SYS> {
+    fn name(&self) -> &str {
+        self.tableau.name()
+    }
+
+    fn order(&self) -> usize {
+        self.tableau.order()
+    }
+
+    fn stages(&self) -> usize {
+        self.tableau.stages()
+    }
 
-        change_term
-            .scalar_mul(h)
-            .pointwise_add(last_values.iter().copied().collect())
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        self.tableau.stability_function(z)
     }
 }
 
@@ -93,80 +351,80 @@ impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> OneStepMethodStep<FT>
 ///
 ///    let approximation = rk_method.interval(3.14, 0);
 /// ```
-pub fn make_explicit_runge_kutta_with_tableau<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    ivp: InitialValueSystemProblem<FT>,
+pub fn make_explicit_runge_kutta_with_tableau<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
-    tableau: Tableau,
-) -> OneStepMethod<FT, ExplicitRungeKuttaMethod<FT>> {
+    tableau: Tableau<S::Scalar>,
+) -> OneStepMethod<SYS, ExplicitRungeKuttaMethod<SYS, S::Scalar>, S> {
     OneStepMethod::new(ExplicitRungeKuttaMethod::new(tableau), ivp, h)
 }
 
 /// Classic 4-th order explicit RK method.
-pub fn make_classic_runge_kutta<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    ivp: InitialValueSystemProblem<FT>,
+/// Works on every `StateVector`, so e.g. nalgebra vectors can be used without conversion.
+///
+/// # Example
+/// ```
+/// use nalgebra::Vector2;
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, SampleableFunction};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+///     dydt[0] = y[1];
+///     dydt[1] = -y[0];
+/// };
+///
+/// let with_nalgebra = InitialValueSystemProblem::new(0.0, Vector2::new(1.0, 0.0), rhs);
+/// let with_array = InitialValueSystemProblem::new(0.0, [1.0, 0.0], rhs);
+///
+/// let y: Vector2<f64> = make_classic_runge_kutta(with_nalgebra, 0.01).value_at(1.0);
+/// let y_array: [f64; 2] = make_classic_runge_kutta(with_array, 0.01).value_at(1.0);
+///
+/// assert!((y[0] - 1.0f64.cos()).abs() < 1e-8);
+/// assert_eq!(y.as_slice(), &y_array[..]);
+/// ```
+pub fn make_classic_runge_kutta<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
-) -> OneStepMethod<FT, ExplicitRungeKuttaMethod<FT>> {
-    let tableau = Tableau::new(
-        vec![0.0, 0.5, 0.5, 1.0],                         // cs
-        vec![1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0], // bs
-        vec![vec![], vec![0.5], vec![0.0, 0.5], vec![0.0, 0.0, 1.0]],
-    );
-
-    OneStepMethod::new(ExplicitRungeKuttaMethod::new(tableau), ivp, h)
+) -> OneStepMethod<SYS, ExplicitRungeKuttaMethod<SYS, S::Scalar>, S> {
+    OneStepMethod::new(ExplicitRungeKuttaMethod::new(Tableau::classic()), ivp, h)
 }
 
 /// England explicit RK method.
-pub fn make_england_runge_kutta<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    ivp: InitialValueSystemProblem<FT>,
+pub fn make_england_runge_kutta<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
-) -> OneStepMethod<FT, ExplicitRungeKuttaMethod<FT>> {
-    let tableau = Tableau::new(
-        vec![0.0, 0.5, 0.5, 1.0],                   // cs
-        vec![1.0 / 6.0, 0.0, 2.0 / 3.0, 1.0 / 6.0], // bs
-        vec![vec![], vec![0.5], vec![0.25, 0.25], vec![0.0, -1.0, 2.0]],
-    );
-
-    OneStepMethod::new(ExplicitRungeKuttaMethod::new(tableau), ivp, h)
+) -> OneStepMethod<SYS, ExplicitRungeKuttaMethod<SYS, S::Scalar>, S> {
+    OneStepMethod::new(ExplicitRungeKuttaMethod::new(Tableau::england()), ivp, h)
 }
 
 /// 3/8 explicit RK method.
-pub fn make_three_eight_runge_kutta<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    ivp: InitialValueSystemProblem<FT>,
+pub fn make_three_eight_runge_kutta<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
-) -> OneStepMethod<FT, ExplicitRungeKuttaMethod<FT>> {
-    let tableau = Tableau::new(
-        vec![0.0, 0.5, 1.0, 1.0],                   // cs
-        vec![1.0 / 6.0, 2.0 / 3.0, 0.0, 1.0 / 6.0], // bs
-        vec![vec![], vec![0.5], vec![0.0, 1.0], vec![0.0, 0.0, 1.0]],
-    );
-
-    OneStepMethod::new(ExplicitRungeKuttaMethod::new(tableau), ivp, h)
+) -> OneStepMethod<SYS, ExplicitRungeKuttaMethod<SYS, S::Scalar>, S> {
+    OneStepMethod::new(
+        ExplicitRungeKuttaMethod::new(Tableau::three_eight()),
+        ivp,
+        h,
+    )
 }
 
 /// 2 Step RK Method of order 2.
-pub fn make_2nd_order_runge_kutta<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    ivp: InitialValueSystemProblem<FT>,
+pub fn make_2nd_order_runge_kutta<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
-) -> OneStepMethod<FT, ExplicitRungeKuttaMethod<FT>> {
-    let tableau = Tableau::new(
-        vec![0.0, 0.5], // cs
-        vec![0.0, 1.0], // bs
-        vec![vec![], vec![0.5]],
-    );
-
-    OneStepMethod::new(ExplicitRungeKuttaMethod::new(tableau), ivp, h)
+) -> OneStepMethod<SYS, ExplicitRungeKuttaMethod<SYS, S::Scalar>, S> {
+    OneStepMethod::new(
+        ExplicitRungeKuttaMethod::new(Tableau::second_order()),
+        ivp,
+        h,
+    )
 }
 
 /// Heun Method of order 3.
-pub fn make_heun_method<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    ivp: InitialValueSystemProblem<FT>,
+pub fn make_heun_method<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
-) -> OneStepMethod<FT, ExplicitRungeKuttaMethod<FT>> {
-    let tableau = Tableau::new(
-        vec![0.0, 1.0 / 3.0, 2.0 / 3.0], // cs
-        vec![0.25, 0.0, 0.75],           // bs
-        vec![vec![], vec![1.0 / 3.0], vec![0.0, 2.0 / 3.0]],
-    );
-
-    OneStepMethod::new(ExplicitRungeKuttaMethod::new(tableau), ivp, h)
+) -> OneStepMethod<SYS, ExplicitRungeKuttaMethod<SYS, S::Scalar>, S> {
+    OneStepMethod::new(ExplicitRungeKuttaMethod::new(Tableau::heun()), ivp, h)
 }
@@ -1,4 +1,4 @@
-use crate::definitions::BoundaryValueProblem;
+use crate::definitions::{BoundaryValueProblem, SolverError};
 use crate::util::make_supporting_points;
 use nalgebra::DMatrix;
 use rayon::prelude::*;
@@ -22,10 +22,10 @@ use rayon::prelude::*;
 /// let bvp = BoundaryValueProblem::new(|x| x.sqrt(), interval, 1.0, 0.0);
 /// let n_grid = 99;
 ///
-/// // Calling the method to get a solution. Could be unsolvable -> expect with error message.
+/// // Calling the method to get a solution. Could be unsolvable -> SolverError::SingularMatrix.
 /// let solution = solve_bvp(bvp, n_grid).expect("No solution found!");
 /// ```
-pub fn solve_bvp(problem: BoundaryValueProblem, n_grid: usize) -> Option<Vec<f64>> {
+pub fn solve_bvp(problem: BoundaryValueProblem, n_grid: usize) -> Result<Vec<f64>, SolverError> {
     // Setting up the sampled points
     let h = problem.interval.span() / (n_grid as f64 + 1.0);
     let grid_x_values = &make_supporting_points(n_grid + 1, problem.interval)[1..=n_grid];
@@ -58,4 +58,5 @@ pub fn solve_bvp(problem: BoundaryValueProblem, n_grid: usize) -> Option<Vec<f64
     decomposition
         .solve(&to_solve)
         .map(|mat| mat.iter().copied().collect())
+        .ok_or(SolverError::SingularMatrix)
 }
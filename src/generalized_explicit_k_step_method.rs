@@ -1,87 +1,728 @@
-use crate::definitions::{InitialValueSystemProblem, ODEMethod, Point2D, SampleableFunction};
+use crate::definitions::{
+    check_dimension, check_finite, InitialValueSystemProblem, ODEMethod, OdeSystem,
+    SampleableFunction, Scalar, SolverError, StateVector,
+};
+use crate::dense_output::DenseSolution;
+use crate::events::{Event, EventDetector, EventOccurrence, SolutionWithEvents};
 use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
+use crate::method_info::MethodInfo;
+use crate::observer::{proceed, Observer, ProgressReporter, StepsOnly};
+use crate::sink::SolutionSink;
+use crate::solution::{OutputTimes, Solution};
+use crate::stats::SolverStats;
+use crate::stepper::Stepper;
+use crate::util::{before_target, directed_step};
 use derive_new::*;
+use num::complex::Complex64;
 
-pub trait KStepMethodStep<FT: SampleableFunction<(f64, Vec<f64>), f64>> {
-    fn step(&self, k: usize, dfs: &[FT], t: f64, last_values: &[Vec<f64>], h: f64) -> Vec<f64>;
+pub trait KStepMethodStep<SYS: OdeSystem<S::Scalar>, S: StateVector = Vec<f64>> {
+    /// Takes a step of size h from the last k values, the newest one at t, and counts its work in `stats`.
+    /// last_slopes holds f at the last values, so only new evaluations of f are needed.
+    fn step(
+        &self,
+        system: &SYS,
+        t: f64,
+        last_values: &[S],
+        last_slopes: &[S],
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> S;
+
+    /// Whether `step` reads last_slopes, methods that evaluate f by hand can skip them.
+    fn uses_slopes(&self) -> bool {
+        true
+    }
+}
+
+/// The last k values of a multistep method and f at them, oldest first.
+/// The slope of a value is only evaluated once, when the first step needs it.
+struct History<S> {
+    k: usize,
+    values: Vec<S>,
+    slopes: Vec<S>,
 }
 
+impl<S: StateVector> History<S> {
+    fn new(k: usize, values: S) -> Self {
+        History {
+            k,
+            values: vec![values],
+            slopes: Vec::with_capacity(k),
+        }
+    }
+
+    /// Appends the newest value, dropping the oldest one if there are k already.
+    fn push(&mut self, values: S) {
+        if self.values.len() == self.k {
+            self.values.remove(0);
+            if !self.slopes.is_empty() {
+                self.slopes.remove(0);
+            }
+        }
+        self.values.push(values);
+    }
+
+    /// Takes a step of the method from the newest value at t.
+    fn step<SYS: OdeSystem<S::Scalar>, STEP: KStepMethodStep<SYS, S>>(
+        &mut self,
+        step_method: &STEP,
+        system: &SYS,
+        t: f64,
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> S {
+        let newest = self.values.len() - 1;
+        let missing = if step_method.uses_slopes() {
+            self.slopes.len()..self.values.len()
+        } else {
+            0..0
+        };
+        for idx in missing {
+            let t_idx = t - (newest - idx) as f64 * h;
+            self.slopes.push(system.rhs_state(t_idx, &self.values[idx]));
+            stats.rhs_evaluations += 1;
+        }
+        step_method.step(system, t, &self.values, &self.slopes, h, stats)
+    }
+}
+
+/// Generates a fresh copy of the problem for every run.
+pub type ProblemGenerator<SYS, S = Vec<f64>> = Box<dyn Fn() -> InitialValueSystemProblem<SYS, S>>;
+/// Builds the one step method used to bootstrap the first k - 1 values.
+pub type StartMethodGenerator<SYS, StartStep, S = Vec<f64>> =
+    Box<dyn Fn(InitialValueSystemProblem<SYS, S>, f64) -> OneStepMethod<SYS, StartStep, S>>;
+
 #[derive(new)]
 pub struct KStepMethod<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
-    STEP: KStepMethodStep<FT>,
-    StartStep: OneStepMethodStep<FT>,
+    SYS: OdeSystem<S::Scalar>,
+    STEP: KStepMethodStep<SYS, S>,
+    StartStep: OneStepMethodStep<SYS, S>,
+    S: StateVector = Vec<f64>,
 > {
-    ivp_getter: fn() -> InitialValueSystemProblem<FT>,
+    ivp_getter: ProblemGenerator<SYS, S>,
     h: f64,
     k: usize,
     step_method: STEP,
-    start_method_gen:
-        fn(ivp: InitialValueSystemProblem<FT>, h: f64) -> OneStepMethod<FT, StartStep>,
+    start_method_gen: StartMethodGenerator<SYS, StartStep, S>,
 }
 
 impl<
-        FT: SampleableFunction<(f64, Vec<f64>), f64>,
-        STEP: KStepMethodStep<FT>,
-        StartStep: OneStepMethodStep<FT>,
-    > ODEMethod for KStepMethod<FT, STEP, StartStep>
+        SYS: OdeSystem<S::Scalar>,
+        STEP: KStepMethodStep<SYS, S>,
+        StartStep: OneStepMethodStep<SYS, S>,
+        S: StateVector,
+    > KStepMethod<SYS, STEP, StartStep, S>
 {
-    fn interval(&self, t_target: f64, skip_n: isize) -> Vec<Vec<Point2D>> {
+    /// Integrates until t_target is passed and hands every skip_n-th value to `output`.
+    /// t_target may also lie before the start time, then the steps go backwards.
+    /// The start values up to t_target and the value at t_target are always passed on,
+    /// an empty interval only passes on the start value.
+    /// If the grid misses t_target, the last step passes it and the value there is interpolated
+    /// from the last k values, that step is reported to the observer but its value is not passed on.
+    /// The same holds for start values past t_target.
+    /// Stops early at the first terminal event, its state is the last one passed on.
+    /// The observer sees every step, including the ones of the start method, and every value passed on.
+    /// The work is counted in `stats`, the start values count as one step each.
+    fn integrate(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event<S::Scalar>],
+        observer: &mut dyn Observer<S::Scalar>,
+        stats: &mut SolverStats,
+        mut output: impl FnMut(f64, &S) -> Result<(), SolverError>,
+    ) -> Result<Vec<EventOccurrence<S::Scalar>>, SolverError> {
         let ivp = (self.ivp_getter)();
+        check_dimension(&ivp)?;
 
+        let mut output = |observer: &mut dyn Observer<S::Scalar>, t: f64, values: &S| {
+            proceed(observer.output(t, values.as_slice()), t)?;
+            output(t, values)
+        };
+
+        let h = directed_step(self.h, ivp.start_time, t_target);
         // Bootstrap with start method (k-1) values
         // Assume h is always small enough for this
         let start_method = (self.start_method_gen)((self.ivp_getter)(), self.h);
-        let mut current_values: Vec<Vec<f64>> = vec![ivp.start_values];
+        let mut detector =
+            EventDetector::watch(events, ivp.start_time, ivp.start_values.as_slice());
+        let start_time = ivp.start_time;
+        // No skipping for the start values
+        output(observer, start_time, &ivp.start_values)?;
+        if t_target == start_time {
+            return Ok(Vec::new());
+        }
+        // Time of the last value passed on
+        let mut t_output = start_time;
+        let mut history = History::new(self.k, ivp.start_values);
         // k not in the range
         for idx in 1..self.k {
-            current_values.push(start_method.value_at(ivp.start_time + idx as f64 * self.h));
+            let t_start = start_time + idx as f64 * h;
+            let mut work = SolverStats::default();
+            let values = start_method.counted_value_at(t_start, &mut work)?;
+            stats.add_work(&work);
+            stats.accept(h);
+            proceed(observer.accepted(t_start, h, values.as_slice()), t_start)?;
+            if before_target(t_target, t_start, h) {
+                // Only needed to interpolate the value at t_target
+                history.push(values);
+                continue;
+            }
+            if let Some(detector) = detector.as_mut() {
+                if let Some((t_event, values_event)) =
+                    detector.check_state(&ivp.dfs, t_start, &values, stats)
+                {
+                    output(observer, t_event, &values_event)?;
+                    return Ok(detector.take_occurrences());
+                }
+            }
+            output(observer, t_start, &values)?;
+            t_output = t_start;
+            history.push(values);
         }
 
         let mut skip: isize = skip_n;
-        let mut t = ivp.start_time + self.k as f64 * self.h;
+        // Index and time of the newest value, t is computed from the index to not accumulate rounding errors
+        let mut n = self.k - 1;
+        let mut t = start_time + n as f64 * h;
 
-        let mut intermediate_values: Vec<Vec<Point2D>> =
-            Vec::with_capacity(((t_target - ivp.start_time) / self.h).ceil() as usize);
+        while before_target(t, t_target, h) {
+            let next_values = history.step(&self.step_method, &ivp.dfs, t, h, stats);
+            history.push(next_values);
 
-        // No skipping for the start values
-        current_values.clone().iter().for_each(|v| {
-            intermediate_values.push(v.iter().map(|val| Point2D { x: t, y: *val }).collect())
-        });
-
-        while t < t_target {
-            let tmp = self
-                .step_method
-                .step(self.k, &ivp.dfs, t, &current_values, self.h);
-            current_values.remove(0);
-            current_values.push(tmp);
-
-            t += self.h;
+            n += 1;
+            t = start_time + n as f64 * h;
+            let newest = history.values.last().unwrap();
+            check_finite(t, newest)?;
+            stats.accept(h);
+            proceed(observer.accepted(t, h, newest.as_slice()), t)?;
+            if before_target(t_target, t, h) {
+                // Passed t_target, the value there is interpolated below
+                break;
+            }
+            if let Some(detector) = detector.as_mut() {
+                if let Some((t_event, values_event)) =
+                    detector.check_state(&ivp.dfs, t, newest, stats)
+                {
+                    output(observer, t_event, &values_event)?;
+                    return Ok(detector.take_occurrences());
+                }
+            }
             skip -= 1;
             if skip <= 0 {
-                intermediate_values.push(
-                    current_values
-                        .last()
-                        .unwrap()
-                        .iter()
-                        .map(|val| Point2D { x: t, y: *val })
-                        .collect(),
-                );
+                output(observer, t, newest)?;
+                t_output = t;
                 skip = skip_n
             }
         }
 
-        intermediate_values.push(
-            current_values
-                .last()
-                .unwrap()
-                .iter()
-                .map(|val| Point2D {
-                    x: t_target,
-                    y: *val,
-                })
-                .collect(),
+        // Already passed on if t_target is one of the start values
+        if t_output != t_target {
+            if before_target(t_target, t, h) {
+                let values = interpolate(&history.values, t, h, t_target);
+                if let Some(detector) = detector.as_mut() {
+                    if let Some((t_event, values_event)) =
+                        detector.check_state(&ivp.dfs, t_target, &values, stats)
+                    {
+                        output(observer, t_event, &values_event)?;
+                        return Ok(detector.take_occurrences());
+                    }
+                }
+                output(observer, t_target, &values)?;
+            } else {
+                output(observer, t, history.values.last().unwrap())?;
+            }
+        }
+        Ok(detector.map_or_else(Vec::new, |mut detector| detector.take_occurrences()))
+    }
+
+    /// Like `value_at`, but reports failures instead of panicking.
+    pub fn try_value_at(&self, t_target: f64) -> Result<S, SolverError> {
+        let mut last = None;
+        // Only the start and end values are reported
+        self.integrate(
+            t_target,
+            isize::MAX,
+            &[],
+            &mut ProgressReporter::default(),
+            &mut SolverStats::default(),
+            |_, values| {
+                last = Some(values.clone());
+                Ok(())
+            },
+        )?;
+        Ok(last.unwrap())
+    }
+
+    /// Like `ODEMethod::interval`, but watches for the zero crossings of the events.
+    /// Returns the solution together with all located crossings.
+    /// If a terminal event occurs, the solution ends with the state at that event.
+    pub fn interval_with_events(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event<S::Scalar>],
+    ) -> Result<SolutionWithEvents<S::Scalar>, SolverError> {
+        self.run(t_target, skip_n, events, &mut ProgressReporter::default())
+    }
+
+    /// Like `ODEMethod::interval`, but reports every step to the observer, which may also cancel the run.
+    pub fn interval_observed<O: Observer<S::Scalar>>(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        observer: &mut O,
+    ) -> Result<Solution<S::Scalar>, SolverError> {
+        Ok(self.run(t_target, skip_n, &[], observer)?.0)
+    }
+
+    /// Collects every skip_n-th state up to t_target, the common part of the `interval` variants.
+    pub(crate) fn run(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event<S::Scalar>],
+        observer: &mut dyn Observer<S::Scalar>,
+    ) -> Result<SolutionWithEvents<S::Scalar>, SolverError> {
+        let ivp = (self.ivp_getter)();
+        check_dimension(&ivp)?;
+        let mut solution = Solution::new(ivp.start_values.dim());
+        let mut stats = SolverStats::default();
+        let occurrences = self.integrate(
+            t_target,
+            skip_n,
+            events,
+            observer,
+            &mut stats,
+            |t, values| {
+                solution.push(t, values.as_slice());
+                Ok(())
+            },
+        )?;
+        solution.set_stats(stats);
+        Ok((solution, occurrences))
+    }
+
+    /// Interpolates the grid with cubic Hermite polynomials to hit the output times.
+    pub(crate) fn run_at(
+        &self,
+        output: &OutputTimes,
+        observer: &mut dyn Observer<S::Scalar>,
+    ) -> Result<Solution<S::Scalar>, SolverError> {
+        let ivp = (self.ivp_getter)();
+        check_dimension(&ivp)?;
+        let times = output.times(ivp.start_time)?;
+        let mut solution = Solution::with_capacity(ivp.start_values.dim(), times.len());
+        let t_last = match times.last() {
+            Some(t_last) => *t_last,
+            None => return Ok(solution),
+        };
+
+        let mut pending = times.iter().copied().peekable();
+        // Time, state and slope of the last grid point
+        let mut last: Option<(f64, S, S)> = None;
+        let mut stats = SolverStats::default();
+        let mut slope_evaluations = 0;
+        self.integrate(
+            t_last,
+            1,
+            &[],
+            &mut StepsOnly(&mut *observer),
+            &mut stats,
+            |t, values| {
+                let slope = ivp.dfs.rhs_state(t, values);
+                slope_evaluations += 1;
+                match &last {
+                    None => {
+                        while let Some(t_out) = pending.next_if(|t_out| *t_out == t) {
+                            solution.push(t_out, values.as_slice());
+                        }
+                    }
+                    Some((t_prev, values_prev, slope_prev)) => {
+                        let mut step = DenseSolution::new(*t_prev, values_prev.as_slice(), 3);
+                        step.push_hermite(
+                            t,
+                            values.as_slice(),
+                            slope_prev.as_slice(),
+                            slope.as_slice(),
+                        );
+                        while let Some(t_out) = pending.next_if(|t_out| step.contains(*t_out)) {
+                            solution.push(t_out, &step.value_at(t_out));
+                        }
+                    }
+                }
+                last = Some((t, values.clone(), slope));
+                Ok(())
+            },
+        )?;
+        stats.rhs_evaluations += slope_evaluations;
+        // Every output time lies between the start and t_last, so none should be left over
+        if let Some(t) = pending.next() {
+            return Err(SolverError::InvalidOutputTime { t });
+        }
+        for (t, values) in solution.iter() {
+            proceed(observer.output(t, values), t)?;
+        }
+        solution.set_stats(stats);
+        Ok(solution)
+    }
+
+    /// Like `ODEMethod::interval`, but hands the values to `sink` while stepping instead of collecting them,
+    /// so long runs need only constant memory.
+    /// Returns the stats of the run, as there is no solution to attach them to.
+    pub fn interval_into<K: SolutionSink<S::Scalar>>(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        sink: &mut K,
+    ) -> Result<SolverStats, SolverError> {
+        let mut stats = SolverStats::default();
+        self.integrate(
+            t_target,
+            skip_n,
+            &[],
+            &mut ProgressReporter::default(),
+            &mut stats,
+            |t, values| Ok(sink.write_state(t, values.as_slice())?),
+        )?;
+        sink.finish()?;
+        Ok(stats)
+    }
+}
+
+/// Evaluates the polynomial through the values at the equidistant times t_newest - (k - 1 - j) h at t.
+/// Its degree k - 1 keeps the order of the k-step methods.
+fn interpolate<S: StateVector>(values: &[S], t_newest: f64, h: f64, t: f64) -> S {
+    let k = values.len();
+    // t in units of h, relative to the oldest value
+    let s = (t - t_newest) / h + (k - 1) as f64;
+    let mut result = values[0].zeros_like();
+    for (j, value) in values.iter().enumerate() {
+        let weight: f64 = (0..k)
+            .filter(|m| *m != j)
+            .map(|m| (s - m as f64) / (j as f64 - m as f64))
+            .product();
+        result.axpy(S::Scalar::from_f64(weight), value);
+    }
+    result
+}
+
+impl<
+        SYS: OdeSystem<S::Scalar>,
+        STEP: KStepMethodStep<SYS, S>,
+        StartStep: OneStepMethodStep<SYS, S>,
+        S: StateVector,
+    > KStepMethod<SYS, STEP, StartStep, S>
+{
+    /// Steps through the problem one step at a time, see `Stepper`.
+    /// The first k - 1 steps are taken by the start method.
+    pub fn stepper(&self) -> Result<KStepStepper<'_, SYS, STEP, StartStep, S>, SolverError> {
+        let ivp = (self.ivp_getter)();
+        check_dimension(&ivp)?;
+        check_finite(ivp.start_time, &ivp.start_values)?;
+        let start_method = (self.start_method_gen)((self.ivp_getter)(), self.h);
+        Ok(KStepStepper {
+            method: self,
+            system: ivp.dfs,
+            start_method,
+            t: ivp.start_time,
+            history: History::new(self.k, ivp.start_values),
+            h: self.h,
+            error: None,
+            stats: SolverStats::default(),
+        })
+    }
+}
+
+/// Iterator over the steps of a k-step method, see `Stepper`.
+/// Changing the state or h restarts the bootstrap with the start method from the current state.
+pub struct KStepStepper<
+    'a,
+    SYS: OdeSystem<S::Scalar>,
+    STEP: KStepMethodStep<SYS, S>,
+    StartStep: OneStepMethodStep<SYS, S>,
+    S: StateVector = Vec<f64>,
+> {
+    method: &'a KStepMethod<SYS, STEP, StartStep, S>,
+    system: SYS,
+    start_method: OneStepMethod<SYS, StartStep, S>,
+    t: f64,
+    // The last k values, the newest one at t
+    history: History<S>,
+    h: f64,
+    error: Option<SolverError>,
+    stats: SolverStats,
+}
+
+impl<
+        SYS: OdeSystem<S::Scalar>,
+        STEP: KStepMethodStep<SYS, S>,
+        StartStep: OneStepMethodStep<SYS, S>,
+        S: StateVector,
+    > KStepStepper<'_, SYS, STEP, StartStep, S>
+{
+    fn restart(&mut self, values: S) {
+        let ivp =
+            InitialValueSystemProblem::new(self.t, values.clone(), (self.method.ivp_getter)().dfs);
+        self.start_method = (self.method.start_method_gen)(ivp, self.h.abs());
+        self.history = History::new(self.method.k, values);
+    }
+
+    fn try_step(&mut self) -> Result<S, SolverError> {
+        if self.history.values.len() < self.method.k {
+            // Still bootstrapping
+            let mut work = SolverStats::default();
+            let values = self
+                .start_method
+                .counted_value_at(self.t + self.h, &mut work)?;
+            self.stats.add_work(&work);
+            return Ok(values);
+        }
+        Ok(self.history.step(
+            &self.method.step_method,
+            &self.system,
+            self.t,
+            self.h,
+            &mut self.stats,
+        ))
+    }
+}
+
+impl<
+        SYS: OdeSystem<S::Scalar>,
+        STEP: KStepMethodStep<SYS, S>,
+        StartStep: OneStepMethodStep<SYS, S>,
+        S: StateVector,
+    > Iterator for KStepStepper<'_, SYS, STEP, StartStep, S>
+{
+    type Item = (f64, S);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        let values = self
+            .try_step()
+            .and_then(|values| check_finite(self.t + self.h, &values).map(|_| values));
+        match values {
+            Ok(values) => {
+                self.stats.accept(self.h);
+                self.t += self.h;
+                self.history.push(values.clone());
+                Some((self.t, values))
+            }
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+impl<
+        SYS: OdeSystem<S::Scalar>,
+        STEP: KStepMethodStep<SYS, S>,
+        StartStep: OneStepMethodStep<SYS, S>,
+        S: StateVector,
+    > Stepper<S> for KStepStepper<'_, SYS, STEP, StartStep, S>
+{
+    fn t(&self) -> f64 {
+        self.t
+    }
+
+    fn state(&self) -> &S {
+        // There always is at least the start value
+        self.history.values.last().unwrap()
+    }
+
+    fn set_state(&mut self, values: S) {
+        self.restart(values);
+    }
+
+    fn h(&self) -> f64 {
+        self.h
+    }
+
+    fn set_h(&mut self, h: f64) {
+        self.h = h;
+        let values = self.state().clone();
+        self.restart(values);
+    }
+
+    fn error(&self) -> Option<SolverError> {
+        self.error
+    }
+
+    fn stats(&self) -> SolverStats {
+        self.stats
+    }
+}
+
+impl<
+        SYS: OdeSystem<S::Scalar>,
+        STEP: KStepMethodStep<SYS, S> + MethodInfo,
+        StartStep: OneStepMethodStep<SYS, S>,
+        S: StateVector,
+    > MethodInfo for KStepMethod<SYS, STEP, StartStep, S>
+{
+    fn name(&self) -> &str {
+        self.step_method.name()
+    }
+
+    fn order(&self) -> usize {
+        self.step_method.order()
+    }
+
+    fn stages(&self) -> usize {
+        self.step_method.stages()
+    }
+
+    fn steps(&self) -> usize {
+        self.k
+    }
+
+    fn is_explicit(&self) -> bool {
+        self.step_method.is_explicit()
+    }
+
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        self.step_method.stability_function(z)
+    }
+}
+
+impl<
+        SYS: OdeSystem,
+        STEP: KStepMethodStep<SYS, S>,
+        StartStep: OneStepMethodStep<SYS, S>,
+        S: StateVector<Scalar = f64>,
+    > ODEMethod for KStepMethod<SYS, STEP, StartStep, S>
+{
+    fn interval(&self, t_target: f64, skip_n: isize) -> Result<Solution, SolverError> {
+        Ok(self
+            .run(t_target, skip_n, &[], &mut ProgressReporter::default())?
+            .0)
+    }
+
+    /// The grid is interpolated with cubic Hermite polynomials to hit the output times.
+    fn interval_at(&self, output: &OutputTimes) -> Result<Solution, SolverError> {
+        self.run_at(output, &mut ProgressReporter::default())
+    }
+}
+
+impl<
+        SYS: OdeSystem<S::Scalar>,
+        STEP: KStepMethodStep<SYS, S>,
+        StartStep: OneStepMethodStep<SYS, S>,
+        S: StateVector,
+    > SampleableFunction<f64, S> for KStepMethod<SYS, STEP, StartStep, S>
+{
+    /// # Panics
+    /// If the method fails, see `try_value_at`.
+    fn value_at(&self, t_target: f64) -> S {
+        self.try_value_at(t_target)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adams_bashforth::make_adams_bashforth_3_method;
+    use crate::explicit_runge_kutta::make_classic_runge_kutta;
+
+    fn decay() -> InitialValueSystemProblem<impl OdeSystem + Clone> {
+        let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+        InitialValueSystemProblem::new(0.0, vec![1.0], rhs)
+    }
+
+    #[test]
+    fn test_target_among_start_values() {
+        // The start values of the 3-step method lie at 0, 0.1 and 0.2
+        let solution = make_adams_bashforth_3_method(decay, 0.1, make_classic_runge_kutta)
+            .interval(0.15, 0)
+            .unwrap();
+
+        assert_eq!(solution.times(), &[0.0, 0.1, 0.15]);
+        assert!((solution.final_state().unwrap()[0] - (-0.15f64).exp()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_empty_interval() {
+        let solution = make_adams_bashforth_3_method(decay, 0.1, make_classic_runge_kutta)
+            .interval(0.0, 0)
+            .unwrap();
+
+        // No start values are computed past the start
+        assert_eq!(solution.times(), &[0.0]);
+        assert_eq!(solution.stats(), &SolverStats::default());
+    }
+
+    #[test]
+    fn test_one_evaluation_per_step() {
+        let method = make_adams_bashforth_3_method(decay, 0.1, make_classic_runge_kutta);
+        let short = method.interval(1.0, 0).unwrap();
+        let long = method.interval(2.0, 0).unwrap();
+
+        // Same start values, the 10 extra steps reuse the slopes of the history
+        assert_eq!(
+            long.stats().rhs_evaluations - short.stats().rhs_evaluations,
+            10
         );
-        intermediate_values
+    }
+
+    #[test]
+    fn test_output_times() {
+        let method = make_adams_bashforth_3_method(decay, 0.1, make_classic_runge_kutta);
+
+        let solution = method
+            .interval_at(&OutputTimes::At(vec![0.0, 0.05, 0.25, 1.0]))
+            .unwrap();
+        assert_eq!(solution.times(), &[0.0, 0.05, 0.25, 1.0]);
+        assert_eq!(
+            method.interval_at(&OutputTimes::At(vec![-0.5, 0.5])),
+            Err(SolverError::InvalidOutputTime { t: 0.5 })
+        );
+    }
+
+    #[test]
+    fn test_target_on_grid() {
+        let solution = make_adams_bashforth_3_method(decay, 0.1, make_classic_runge_kutta)
+            .interval(1.0, 0)
+            .unwrap();
+
+        // 10 * 0.1 is 1 exactly, no interpolated value and no duplicate
+        assert_eq!(solution.len(), 11);
+        assert_eq!(solution.final_time(), Some(1.0));
+        assert!(solution.times().windows(2).all(|ts| ts[0] < ts[1]));
+    }
+
+    #[test]
+    fn test_interpolated_target() {
+        let method = make_adams_bashforth_3_method(decay, 0.1, make_classic_runge_kutta);
+        let error = |t: f64, y: &[f64]| (y[0] - (-t).exp()).abs();
+
+        let solution = method.interval(1.05, 4).unwrap();
+        assert_eq!(solution.final_time(), Some(1.05));
+        assert!(solution.times().windows(2).all(|ts| ts[0] < ts[1]));
+
+        // As accurate as the grid points around it
+        let grid = method.interval(1.1, 0).unwrap();
+        let grid_error = grid
+            .iter()
+            .skip(10)
+            .map(|(t, y)| error(t, y))
+            .fold(0.0, f64::max);
+        assert!(error(1.05, solution.final_state().unwrap()) < 2.0 * grid_error);
+    }
+
+    #[test]
+    fn test_target_backwards() {
+        let solution = make_adams_bashforth_3_method(decay, 0.1, make_classic_runge_kutta)
+            .interval(-1.05, 0)
+            .unwrap();
+
+        assert_eq!(solution.final_time(), Some(-1.05));
+        assert!(solution.times().windows(2).all(|ts| ts[0] > ts[1]));
+        assert!((solution.final_state().unwrap()[0] - 1.05f64.exp()).abs() < 1e-3);
     }
 }
@@ -1,87 +1,783 @@
 use crate::definitions::{
-    DifferentiableFunction, InitialValueSystemProblem, ODEMethod, Point2D, SampleableFunction,
-    SampledDerivative,
+    check_dimension, check_finite, DifferentiableFunction, InitialValueSystemProblem, ODEMethod,
+    OdeSystem, SampleableFunction, SampledDerivative, SolverError, StateVector,
 };
+use crate::dense_output::DenseSolution;
+use crate::events::{Event, EventDetector, EventOccurrence, SolutionWithEvents};
+use crate::method_info::MethodInfo;
+use crate::observer::{proceed, Observer, ProgressReporter};
+use crate::sink::SolutionSink;
+use crate::solution::{OutputTimes, Solution};
+use crate::stats::SolverStats;
+use crate::stepper::Stepper;
+use crate::util::{before_target, directed_step};
 use crate::{abs, ceil};
 use derive_new::*;
-use std::f64::EPSILON;
+use num::complex::Complex64;
+use std::cell::RefCell;
 
-pub trait OneStepMethodStep<FT: SampleableFunction<(f64, Vec<f64>), f64>> {
-    fn step(&self, dfs: &[FT], t: f64, last_values: &[f64], h: f64) -> Vec<f64>;
+pub trait OneStepMethodStep<SYS: OdeSystem<S::Scalar>, S: StateVector = Vec<f64>> {
+    /// Takes a step of size h from (t, last_values) and counts its work in `stats`.
+    /// Fails if an implicit method can not solve for the new values.
+    fn step(
+        &self,
+        system: &SYS,
+        t: f64,
+        last_values: &S,
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> Result<S, SolverError>;
+
+    /// Like `step`, but overwrites values with the new ones and keeps its buffers in the workspace,
+    /// so a run does not allocate in every step.
+    /// Defaults to `step`, the values are unspecified if it fails.
+    fn step_in_place(
+        &self,
+        system: &SYS,
+        t: f64,
+        values: &mut S,
+        h: f64,
+        _workspace: &mut StepWorkspace<S>,
+        stats: &mut SolverStats,
+    ) -> Result<(), SolverError> {
+        *values = self.step(system, t, values, h, stats)?;
+        Ok(())
+    }
+}
+
+/// Buffers shaped like the state that a step method reuses from step to step.
+/// Starts out empty, the buffers are allocated the first time they are asked for.
+pub struct StepWorkspace<S> {
+    buffers: Vec<S>,
+}
+
+impl<S: StateVector> StepWorkspace<S> {
+    /// At least n buffers with the shape of template, their contents are left over from the last use.
+    pub fn buffers(&mut self, n: usize, template: &S) -> &mut [S] {
+        while self.buffers.len() < n {
+            self.buffers.push(template.zeros_like());
+        }
+        &mut self.buffers[..n]
+    }
+}
+
+impl<S> Default for StepWorkspace<S> {
+    fn default() -> Self {
+        StepWorkspace {
+            buffers: Vec::new(),
+        }
+    }
 }
 
 #[derive(new)]
-pub struct OneStepMethod<FT: SampleableFunction<(f64, Vec<f64>), f64>, STEP: OneStepMethodStep<FT>>
-{
+pub struct OneStepMethod<
+    SYS: OdeSystem<S::Scalar>,
+    STEP: OneStepMethodStep<SYS, S>,
+    S: StateVector = Vec<f64>,
+> {
     step_method: STEP,
-    ivp: InitialValueSystemProblem<FT>,
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
 }
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>, STEP: OneStepMethodStep<FT>> ODEMethod
-    for OneStepMethod<FT, STEP>
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector>
+    OneStepMethod<SYS, STEP, S>
 {
-    fn interval(&self, t_target: f64, skip_n: isize) -> Vec<Vec<Point2D>> {
+    /// Integrates up to t_target and hands every skip_n-th value to `output`.
+    /// t_target may also lie before the start time, then the steps go backwards.
+    /// The start and end values are always passed on, for an empty interval only the start value.
+    /// Stops early at the first terminal event, its state is the last one passed on.
+    /// The observer sees every step and every value passed on, the work is counted in `stats`.
+    fn integrate(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event<S::Scalar>],
+        observer: &mut dyn Observer<S::Scalar>,
+        stats: &mut SolverStats,
+        mut output: impl FnMut(f64, &S) -> Result<(), SolverError>,
+    ) -> Result<Vec<EventOccurrence<S::Scalar>>, SolverError> {
+        check_dimension(&self.ivp)?;
+        check_finite(self.ivp.start_time, &self.ivp.start_values)?;
+
+        let mut output = |observer: &mut dyn Observer<S::Scalar>, t: f64, values: &S| {
+            proceed(observer.output(t, values.as_slice()), t)?;
+            output(t, values)
+        };
+
+        let h = directed_step(self.h, self.ivp.start_time, t_target);
         let mut skip: isize = skip_n;
         let mut t = self.ivp.start_time;
         let mut values = self.ivp.start_values.clone();
-        let mut intermediate_values: Vec<Vec<Point2D>> =
-            Vec::with_capacity(((t_target - self.ivp.start_time) / self.h).ceil() as usize);
-        intermediate_values.push(values.iter().map(|val| Point2D { x: t, y: *val }).collect());
+        let mut workspace = StepWorkspace::default();
+        let mut detector = EventDetector::watch(events, t, values.as_slice());
+        output(observer, t, &values)?;
+        if t_target == t {
+            return Ok(Vec::new());
+        }
 
-        while t + self.h < t_target {
-            values = self.step_method.step(&self.ivp.dfs, t, &values, self.h);
+        while before_target(t + h, t_target, h) {
+            self.step_method.step_in_place(
+                &self.ivp.dfs,
+                t,
+                &mut values,
+                h,
+                &mut workspace,
+                stats,
+            )?;
 
-            t += self.h;
+            t += h;
+            check_finite(t, &values)?;
+            stats.accept(h);
+            proceed(observer.accepted(t, h, values.as_slice()), t)?;
+            if let Some(detector) = detector.as_mut() {
+                if let Some((t_event, values_event)) =
+                    detector.check_state(&self.ivp.dfs, t, &values, stats)
+                {
+                    output(observer, t_event, &values_event)?;
+                    return Ok(detector.take_occurrences());
+                }
+            }
             skip -= 1;
             if skip <= 0 {
-                intermediate_values
-                    .push(values.iter().map(|val| Point2D { x: t, y: *val }).collect());
+                output(observer, t, &values)?;
                 skip = skip_n
             }
         }
 
-        values = self
-            .step_method
-            .step(&self.ivp.dfs, t, &values, t_target - t);
-        intermediate_values.push(
-            values
-                .iter()
-                .map(|val| Point2D {
-                    x: t_target,
-                    y: *val,
-                })
-                .collect(),
+        self.step_method.step_in_place(
+            &self.ivp.dfs,
+            t,
+            &mut values,
+            t_target - t,
+            &mut workspace,
+            stats,
+        )?;
+        check_finite(t_target, &values)?;
+        stats.accept(t_target - t);
+        proceed(
+            observer.accepted(t_target, t_target - t, values.as_slice()),
+            t_target,
+        )?;
+        if let Some(detector) = detector.as_mut() {
+            if let Some((t_event, values_event)) =
+                detector.check_state(&self.ivp.dfs, t_target, &values, stats)
+            {
+                output(observer, t_event, &values_event)?;
+                return Ok(detector.take_occurrences());
+            }
+        }
+        output(observer, t_target, &values)?;
+        Ok(detector.map_or_else(Vec::new, |mut detector| detector.take_occurrences()))
+    }
+
+    /// Like `value_at`, but reports failures instead of panicking.
+    pub fn try_value_at(&self, t_target: f64) -> Result<S, SolverError> {
+        self.counted_value_at(t_target, &mut SolverStats::default())
+    }
+
+    /// Like `try_value_at`, but counts the work in `stats`.
+    pub(crate) fn counted_value_at(
+        &self,
+        t_target: f64,
+        stats: &mut SolverStats,
+    ) -> Result<S, SolverError> {
+        let mut last = self.ivp.start_values.clone();
+        // Only the start and end values are reported
+        self.integrate(
+            t_target,
+            isize::MAX,
+            &[],
+            &mut ProgressReporter::default(),
+            stats,
+            |_, values| {
+                last = values.clone();
+                Ok(())
+            },
+        )?;
+        Ok(last)
+    }
+
+    /// Like `ODEMethod::interval`, but watches for the zero crossings of the events.
+    /// Returns the solution together with all located crossings.
+    /// If a terminal event occurs, the solution ends with the state at that event.
+    pub fn interval_with_events(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event<S::Scalar>],
+    ) -> Result<SolutionWithEvents<S::Scalar>, SolverError> {
+        self.run(t_target, skip_n, events, &mut ProgressReporter::default())
+    }
+
+    /// Like `ODEMethod::interval`, but reports every step to the observer, which may also cancel the run.
+    pub fn interval_observed<O: Observer<S::Scalar>>(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        observer: &mut O,
+    ) -> Result<Solution<S::Scalar>, SolverError> {
+        Ok(self.run(t_target, skip_n, &[], observer)?.0)
+    }
+
+    /// Collects every skip_n-th state up to t_target, the common part of the `interval` variants.
+    pub(crate) fn run(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        events: &[Event<S::Scalar>],
+        observer: &mut dyn Observer<S::Scalar>,
+    ) -> Result<SolutionWithEvents<S::Scalar>, SolverError> {
+        check_dimension(&self.ivp)?;
+        let mut solution = Solution::with_capacity(
+            self.ivp.start_values.dim(),
+            ((t_target - self.ivp.start_time) / self.h).abs().ceil() as usize,
         );
-        intermediate_values
+        let mut stats = SolverStats::default();
+        let occurrences = self.integrate(
+            t_target,
+            skip_n,
+            events,
+            observer,
+            &mut stats,
+            |t, values| {
+                solution.push(t, values.as_slice());
+                Ok(())
+            },
+        )?;
+        solution.set_stats(stats);
+        Ok((solution, occurrences))
+    }
+
+    /// Integrates from output time to output time, the last step before each is shortened to hit it.
+    pub(crate) fn run_at(
+        &self,
+        output: &OutputTimes,
+        observer: &mut dyn Observer<S::Scalar>,
+    ) -> Result<Solution<S::Scalar>, SolverError> {
+        check_dimension(&self.ivp)?;
+        check_finite(self.ivp.start_time, &self.ivp.start_values)?;
+
+        let times = output.times(self.ivp.start_time)?;
+        let mut solution = Solution::with_capacity(self.ivp.start_values.dim(), times.len());
+        let mut t = self.ivp.start_time;
+        let mut values = self.ivp.start_values.clone();
+        let mut stats = SolverStats::default();
+        let mut workspace = StepWorkspace::default();
+        for t_out in times {
+            let h = directed_step(self.h, t, t_out);
+            while before_target(t + h, t_out, h) {
+                self.step_method.step_in_place(
+                    &self.ivp.dfs,
+                    t,
+                    &mut values,
+                    h,
+                    &mut workspace,
+                    &mut stats,
+                )?;
+                t += h;
+                check_finite(t, &values)?;
+                stats.accept(h);
+                proceed(observer.accepted(t, h, values.as_slice()), t)?;
+            }
+            if t_out != t {
+                self.step_method.step_in_place(
+                    &self.ivp.dfs,
+                    t,
+                    &mut values,
+                    t_out - t,
+                    &mut workspace,
+                    &mut stats,
+                )?;
+                check_finite(t_out, &values)?;
+                stats.accept(t_out - t);
+                proceed(
+                    observer.accepted(t_out, t_out - t, values.as_slice()),
+                    t_out,
+                )?;
+                t = t_out;
+            }
+            proceed(observer.output(t, values.as_slice()), t)?;
+            solution.push(t, values.as_slice());
+        }
+        solution.set_stats(stats);
+        Ok(solution)
+    }
+
+    /// Like `ODEMethod::interval`, but hands the values to `sink` while stepping instead of collecting them,
+    /// so long runs need only constant memory.
+    /// Returns the stats of the run, as there is no solution to attach them to.
+    pub fn interval_into<K: SolutionSink<S::Scalar>>(
+        &self,
+        t_target: f64,
+        skip_n: isize,
+        sink: &mut K,
+    ) -> Result<SolverStats, SolverError> {
+        let mut stats = SolverStats::default();
+        self.integrate(
+            t_target,
+            skip_n,
+            &[],
+            &mut ProgressReporter::default(),
+            &mut stats,
+            |t, values| Ok(sink.write_state(t, values.as_slice())?),
+        )?;
+        sink.finish()?;
+        Ok(stats)
+    }
+
+    /// Integrates up to t_target and keeps a cubic Hermite interpolant for every step,
+    /// so the result can be sampled at any t in between.
+    /// Costs one additional evaluation of the right-hand side per step.
+    pub fn dense_interval(&self, t_target: f64) -> Result<DenseSolution<S::Scalar>, SolverError> {
+        let mut dense =
+            DenseSolution::new(self.ivp.start_time, self.ivp.start_values.as_slice(), 3);
+        let mut slope_last = self.ivp.start_values.zeros_like();
+        let mut slope = self.ivp.start_values.zeros_like();
+        let mut stats = SolverStats::default();
+        let mut slope_evaluations = 0;
+        self.integrate(
+            t_target,
+            1,
+            &[],
+            &mut ProgressReporter::default(),
+            &mut stats,
+            |t, values| {
+                self.ivp.dfs.rhs(t, values.as_slice(), slope.as_mut_slice());
+                slope_evaluations += 1;
+                if t != self.ivp.start_time {
+                    dense.push_hermite(
+                        t,
+                        values.as_slice(),
+                        slope_last.as_slice(),
+                        slope.as_slice(),
+                    );
+                }
+                std::mem::swap(&mut slope_last, &mut slope);
+                Ok(())
+            },
+        )?;
+        stats.rhs_evaluations += slope_evaluations;
+        dense.set_stats(stats);
+        Ok(dense)
+    }
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector>
+    OneStepMethod<SYS, STEP, S>
+{
+    /// Wraps the method so that the trajectory is only integrated once.
+    /// Later calls to `value_at` and `derivative_at` are answered from the stored grid,
+    /// which is extended lazily if a t beyond it is requested.
+    pub fn cached(self) -> CachedOneStepMethod<SYS, STEP, S> {
+        let trajectory = Trajectory {
+            ts: vec![self.ivp.start_time],
+            states: vec![self.ivp.start_values.clone()],
+        };
+        CachedOneStepMethod {
+            method: self,
+            trajectory: RefCell::new(trajectory),
+        }
+    }
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector>
+    OneStepMethod<SYS, STEP, S>
+{
+    /// Steps through the problem one step at a time, see `Stepper`.
+    pub fn stepper(&self) -> Result<OneStepStepper<'_, SYS, STEP, S>, SolverError> {
+        check_dimension(&self.ivp)?;
+        check_finite(self.ivp.start_time, &self.ivp.start_values)?;
+        Ok(OneStepStepper {
+            method: self,
+            t: self.ivp.start_time,
+            values: self.ivp.start_values.clone(),
+            h: self.h,
+            error: None,
+            stats: SolverStats::default(),
+        })
+    }
+}
+
+/// Iterator over the steps of a one step method, see `Stepper`.
+pub struct OneStepStepper<
+    'a,
+    SYS: OdeSystem<S::Scalar>,
+    STEP: OneStepMethodStep<SYS, S>,
+    S: StateVector = Vec<f64>,
+> {
+    method: &'a OneStepMethod<SYS, STEP, S>,
+    t: f64,
+    values: S,
+    h: f64,
+    error: Option<SolverError>,
+    stats: SolverStats,
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector> Iterator
+    for OneStepStepper<'_, SYS, STEP, S>
+{
+    type Item = (f64, S);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        let values = match self.try_step() {
+            Ok(values) => values,
+            Err(err) => {
+                self.error = Some(err);
+                return None;
+            }
+        };
+        self.stats.accept(self.h);
+
+        self.t += self.h;
+        self.values = values;
+        Some((self.t, self.values.clone()))
+    }
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector>
+    OneStepStepper<'_, SYS, STEP, S>
+{
+    /// The values after the next step.
+    fn try_step(&mut self) -> Result<S, SolverError> {
+        let values = self.method.step_method.step(
+            &self.method.ivp.dfs,
+            self.t,
+            &self.values,
+            self.h,
+            &mut self.stats,
+        )?;
+        check_finite(self.t + self.h, &values)?;
+        Ok(values)
+    }
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector> Stepper<S>
+    for OneStepStepper<'_, SYS, STEP, S>
+{
+    fn t(&self) -> f64 {
+        self.t
+    }
+
+    fn state(&self) -> &S {
+        &self.values
+    }
+
+    fn set_state(&mut self, values: S) {
+        self.values = values;
+    }
+
+    fn h(&self) -> f64 {
+        self.h
+    }
+
+    fn set_h(&mut self, h: f64) {
+        self.h = h;
+    }
+
+    fn error(&self) -> Option<SolverError> {
+        self.error
+    }
+
+    fn stats(&self) -> SolverStats {
+        self.stats
     }
 }
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>, STEP: OneStepMethodStep<FT>>
-    OneStepMethod<FT, STEP>
+impl<SYS: OdeSystem, STEP: OneStepMethodStep<SYS, S>, S: StateVector<Scalar = f64>> ODEMethod
+    for OneStepMethod<SYS, STEP, S>
 {
-    pub fn get_derivative(self) -> SampledDerivative<f64, Vec<f64>, Self> {
+    fn interval(&self, t_target: f64, skip_n: isize) -> Result<Solution, SolverError> {
+        Ok(self
+            .run(t_target, skip_n, &[], &mut ProgressReporter::default())?
+            .0)
+    }
+
+    /// Integrates from output time to output time, the last step before each is shortened to hit it.
+    fn interval_at(&self, output: &OutputTimes) -> Result<Solution, SolverError> {
+        self.run_at(output, &mut ProgressReporter::default())
+    }
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S> + MethodInfo, S: StateVector>
+    MethodInfo for OneStepMethod<SYS, STEP, S>
+{
+    fn name(&self) -> &str {
+        self.step_method.name()
+    }
+
+    fn order(&self) -> usize {
+        self.step_method.order()
+    }
+
+    fn stages(&self) -> usize {
+        self.step_method.stages()
+    }
+
+    fn steps(&self) -> usize {
+        self.step_method.steps()
+    }
+
+    fn is_explicit(&self) -> bool {
+        self.step_method.is_explicit()
+    }
+
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        self.step_method.stability_function(z)
+    }
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector>
+    SampleableFunction<f64, S> for OneStepMethod<SYS, STEP, S>
+{
+    /// # Panics
+    /// If the method fails, see `try_value_at`.
+    fn value_at(&self, t_target: f64) -> S {
+        self.try_value_at(t_target)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl<SYS: OdeSystem, STEP: OneStepMethodStep<SYS, S>, S: StateVector<Scalar = f64>>
+    OneStepMethod<SYS, STEP, S>
+{
+    pub fn get_derivative(self) -> SampledDerivative<f64, S, Self> {
         SampledDerivative::new(self)
     }
 }
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>, STEP: OneStepMethodStep<FT>>
-    DifferentiableFunction<f64, Vec<f64>> for OneStepMethod<FT, STEP>
+impl<SYS: OdeSystem, STEP: OneStepMethodStep<SYS, S>, S: StateVector<Scalar = f64>>
+    DifferentiableFunction<f64, S> for OneStepMethod<SYS, STEP, S>
 {
-    fn derivative_at(&self, t_target: f64) -> Vec<f64> {
+    fn derivative_at(&self, t_target: f64) -> S {
         // We go a step farther, because we want the right slope
-        let results = self.interval(t_target + self.h, 0);
+        let results = self
+            .interval(t_target + self.h, 0)
+            .unwrap_or_else(|err| panic!("{}", err));
         // Get the first x coord that is strictly larger than the target
-        let idx = if abs!(t_target - self.ivp.start_time) > EPSILON {
+        let idx = if abs!(t_target - self.ivp.start_time) > f64::EPSILON {
             ceil!((t_target - self.ivp.start_time) / self.h)
         } else {
             1
         };
         // Calculate line slope
-        results[idx - 1]
-            .iter()
-            .zip(&results[idx])
-            .map(|(p1, p2)| (p2.y - p1.y) / (p2.x - p1.x))
-            .collect()
+        let dt = results.times()[idx] - results.times()[idx - 1];
+        let mut slope = self.ivp.start_values.zeros_like();
+        slope
+            .as_mut_slice()
+            .iter_mut()
+            .zip(results.state(idx - 1).iter().zip(results.state(idx)))
+            .for_each(|(s, (y1, y2))| *s = (y2 - y1) / dt);
+        slope
+    }
+}
+
+struct Trajectory<S> {
+    ts: Vec<f64>,
+    states: Vec<S>,
+}
+
+/// A one step method that remembers its grid, see `OneStepMethod::cached`.
+/// Returns exactly the same values as the wrapped method, but sampling it at N points
+/// only costs O(N) steps instead of O(N²).
+pub struct CachedOneStepMethod<
+    SYS: OdeSystem<S::Scalar>,
+    STEP: OneStepMethodStep<SYS, S>,
+    S: StateVector = Vec<f64>,
+> {
+    method: OneStepMethod<SYS, STEP, S>,
+    trajectory: RefCell<Trajectory<S>>,
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector>
+    CachedOneStepMethod<SYS, STEP, S>
+{
+    /// Extends the grid until it covers t_target, which must not lie before the start.
+    /// Returns the index of the grid point the last step towards t_target starts from.
+    fn extend_to(&self, t_target: f64) -> Result<usize, SolverError> {
+        let method = &self.method;
+        let mut trajectory = self.trajectory.borrow_mut();
+        if trajectory.ts.len() == 1 {
+            check_dimension(&method.ivp)?;
+            check_finite(method.ivp.start_time, &method.ivp.start_values)?;
+        }
+
+        // There always is at least the start value
+        let mut t = *trajectory.ts.last().unwrap();
+        while t + method.h < t_target {
+            let values = method.step_method.step(
+                &method.ivp.dfs,
+                t,
+                trajectory.states.last().unwrap(),
+                method.h,
+                &mut SolverStats::default(),
+            )?;
+            t += method.h;
+            check_finite(t, &values)?;
+            trajectory.ts.push(t);
+            trajectory.states.push(values);
+        }
+
+        Ok(trajectory
+            .ts
+            .partition_point(|t_i| t_i + method.h < t_target))
+    }
+
+    /// Like `value_at`, but reports failures instead of panicking.
+    /// Only the grid after the start is stored, targets before it are integrated without caching.
+    pub fn try_value_at(&self, t_target: f64) -> Result<S, SolverError> {
+        if t_target < self.method.ivp.start_time {
+            return self.method.try_value_at(t_target);
+        }
+        let idx = self.extend_to(t_target)?;
+        let trajectory = self.trajectory.borrow();
+        let t = trajectory.ts[idx];
+        let values = self.method.step_method.step(
+            &self.method.ivp.dfs,
+            t,
+            &trajectory.states[idx],
+            t_target - t,
+            &mut SolverStats::default(),
+        )?;
+        check_finite(t_target, &values)?;
+        Ok(values)
+    }
+}
+
+impl<SYS: OdeSystem<S::Scalar>, STEP: OneStepMethodStep<SYS, S>, S: StateVector>
+    SampleableFunction<f64, S> for CachedOneStepMethod<SYS, STEP, S>
+{
+    /// # Panics
+    /// If the method fails, see `try_value_at`.
+    fn value_at(&self, t_target: f64) -> S {
+        self.try_value_at(t_target)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl<SYS: OdeSystem, STEP: OneStepMethodStep<SYS, S>, S: StateVector<Scalar = f64>>
+    CachedOneStepMethod<SYS, STEP, S>
+{
+    pub fn get_derivative(self) -> SampledDerivative<f64, S, Self> {
+        SampledDerivative::new(self)
+    }
+}
+
+impl<SYS: OdeSystem, STEP: OneStepMethodStep<SYS, S>, S: StateVector<Scalar = f64>>
+    DifferentiableFunction<f64, S> for CachedOneStepMethod<SYS, STEP, S>
+{
+    /// Same slope as `OneStepMethod::derivative_at`, taken from the stored grid.
+    fn derivative_at(&self, t_target: f64) -> S {
+        let start_time = self.method.ivp.start_time;
+        let h = self.method.h;
+        // We go a step farther, because we want the right slope
+        let t_end = t_target + h;
+        let last_idx = self
+            .extend_to(t_end)
+            .unwrap_or_else(|err| panic!("{}", err));
+        // Get the first x coord that is strictly larger than the target
+        let idx = if abs!(t_target - start_time) > f64::EPSILON {
+            ceil!((t_target - start_time) / h)
+        } else {
+            1
+        };
+
+        // Grid point or, past the grid, the end point at t_end
+        let point = |i: usize| {
+            if i <= last_idx {
+                let trajectory = self.trajectory.borrow();
+                (trajectory.ts[i], trajectory.states[i].clone())
+            } else {
+                (t_end, self.value_at(t_end))
+            }
+        };
+        let (t1, y1) = point(idx - 1);
+        let (t2, y2) = point(idx);
+
+        // Calculate line slope
+        let dt = t2 - t1;
+        let mut slope = y1.zeros_like();
+        slope
+            .as_mut_slice()
+            .iter_mut()
+            .zip(y1.as_slice().iter().zip(y2.as_slice()))
+            .for_each(|(s, (y1, y2))| *s = (y2 - y1) / dt);
+        slope
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definitions::{InitialValueSystemProblem, ODEMethod, SolverError};
+    use crate::explicit_runge_kutta::make_classic_runge_kutta;
+    use crate::stats::SolverStats;
+
+    #[test]
+    fn test_empty_interval() {
+        let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+        let problem = InitialValueSystemProblem::new(0.0, vec![1.0], rhs);
+        let solution = make_classic_runge_kutta(problem, 0.1)
+            .interval(0.0, 0)
+            .unwrap();
+
+        // Only the start value, no zero-length step
+        assert_eq!(solution.times(), &[0.0]);
+        assert_eq!(solution.final_state(), Some(&[1.0][..]));
+        assert_eq!(solution.stats(), &SolverStats::default());
+    }
+
+    #[test]
+    fn test_empty_state() {
+        let rhs = |_t: f64, _y: &[f64], _dydt: &mut [f64]| {};
+        let problem = InitialValueSystemProblem::new(0.0, Vec::new(), rhs);
+
+        assert_eq!(
+            make_classic_runge_kutta(problem, 0.1)
+                .interval(1.0, 0)
+                .map(|_| ()),
+            Err(SolverError::DimensionMismatch {
+                expected: 1,
+                found: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_cache_reuse() {
+        let rhs = |t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = t * y[0];
+        let problem = InitialValueSystemProblem::new(0.0, vec![1.0], rhs);
+        let uncached = make_classic_runge_kutta(problem.clone(), 0.1);
+        let cached = make_classic_runge_kutta(problem, 0.1).cached();
+        let grid_len = || cached.trajectory.borrow().ts.len();
+
+        assert_eq!(
+            cached.try_value_at(0.75).unwrap(),
+            uncached.try_value_at(0.75).unwrap()
+        );
+        let len = grid_len();
+        assert_eq!(len, 8);
+        // Earlier times are answered from the grid
+        for t in [0.35, 0.1, 0.0, 0.7] {
+            assert_eq!(
+                cached.try_value_at(t).unwrap(),
+                uncached.try_value_at(t).unwrap()
+            );
+        }
+        assert_eq!(grid_len(), len);
+        // Later ones extend it
+        assert_eq!(
+            cached.try_value_at(1.25).unwrap(),
+            uncached.try_value_at(1.25).unwrap()
+        );
+        assert_eq!(grid_len(), 13);
+        // Times before the start are not stored
+        assert_eq!(
+            cached.try_value_at(-0.5).unwrap(),
+            uncached.try_value_at(-0.5).unwrap()
+        );
+        assert_eq!(grid_len(), 13);
     }
 }
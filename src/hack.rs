@@ -1,90 +1,191 @@
-use crate::definitions::{InitialValueSystemProblem, SampleableFunction};
+use crate::definitions::{InitialValueSystemProblem, OdeSystem, StateVector};
 use crate::generalized_explicit_k_step_method::{KStepMethod, KStepMethodStep};
 use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
+use crate::method_info::MethodInfo;
+use crate::stats::SolverStats;
 use crate::{cos, exp};
 use std::f64::consts::E;
 
 /// Hacked together explicit version of AM for task 9, 1
 pub struct AdamsMoultonHack;
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> KStepMethodStep<FT> for AdamsMoultonHack {
-    fn step(&self, _k: usize, _dfs: &[FT], t: f64, last_values: &[Vec<f64>], h: f64) -> Vec<f64> {
-        let nplus2 = last_values[2][0];
-        let nplus1 = last_values[1][0];
-        let n = last_values[0][0];
+impl<SYS: OdeSystem, S: StateVector<Scalar = f64>> KStepMethodStep<SYS, S> for AdamsMoultonHack {
+    fn step(
+        &self,
+        _system: &SYS,
+        t: f64,
+        last_values: &[S],
+        _last_slopes: &[S],
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> S {
+        // The right-hand side is evaluated inline, once per value used
+        stats.rhs_evaluations += 3;
+        let nplus2 = last_values[2].as_slice()[0];
+        let nplus1 = last_values[1].as_slice()[0];
+        let n = last_values[0].as_slice()[0];
 
         let tplus3 = t + h;
         let tplus2 = t;
         let tplus1 = t - h;
         let tplus0 = t - 2.0 * h;
 
-        vec![
-            (nplus2
-                + (h / 24.0)
-                    * (-19.0 * tplus2 * tplus2 * nplus2 + 5.0 * tplus1 * tplus1 * nplus1
-                        - tplus0 * tplus0 * n))
-                / (1.0 + h * 9.0 * tplus3 * tplus3 / 24.0),
-        ]
+        // Only the first component is used
+        let mut next_values = last_values[0].zeros_like();
+        next_values.as_mut_slice()[0] = (nplus2
+            + (h / 24.0)
+                * (-19.0 * tplus2 * tplus2 * nplus2 + 5.0 * tplus1 * tplus1 * nplus1
+                    - tplus0 * tplus0 * n))
+            / (1.0 + h * 9.0 * tplus3 * tplus3 / 24.0);
+        next_values
+    }
+
+    fn uses_slopes(&self) -> bool {
+        false
+    }
+}
+
+impl MethodInfo for AdamsMoultonHack {
+    fn name(&self) -> &str {
+        "Adams-Moulton 4 (hack)"
+    }
+
+    fn order(&self) -> usize {
+        4
+    }
+
+    fn stages(&self) -> usize {
+        3
+    }
+
+    fn steps(&self) -> usize {
+        3
+    }
+
+    /// Implicit, the equation is only solved by hand for the problem of the task
+    fn is_explicit(&self) -> bool {
+        false
     }
 }
 
 /// Makes a system of ODEs into a sampleable function using an Adams-Bashforth method.
 pub fn make_adams_moulton_hack_method<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
-    StartStep: OneStepMethodStep<FT>,
+    SYS: OdeSystem,
+    S: StateVector<Scalar = f64>,
+    StartStep: OneStepMethodStep<SYS, S>,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS, S> + 'static,
+    StartGen: Fn(InitialValueSystemProblem<SYS, S>, f64) -> OneStepMethod<SYS, StartStep, S> + 'static,
 >(
-    ivp: fn() -> InitialValueSystemProblem<FT>,
+    ivp: IvpGen,
     h: f64,
-    start_method_gen: fn(
-        ivp: InitialValueSystemProblem<FT>,
-        h: f64,
-    ) -> OneStepMethod<FT, StartStep>,
-) -> KStepMethod<FT, AdamsMoultonHack, StartStep> {
-    KStepMethod::new(ivp, h, 3, AdamsMoultonHack, start_method_gen)
+    start_method_gen: StartGen,
+) -> KStepMethod<SYS, AdamsMoultonHack, StartStep, S> {
+    KStepMethod::new(
+        Box::new(ivp),
+        h,
+        3,
+        AdamsMoultonHack,
+        Box::new(start_method_gen),
+    )
 }
 
 /// Hacked together explicit version of AM for task 9, 1
 pub struct MilneSimpsonHack;
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> KStepMethodStep<FT> for MilneSimpsonHack {
-    fn step(&self, _k: usize, _dfs: &[FT], t: f64, last_values: &[Vec<f64>], h: f64) -> Vec<f64> {
-        let nplus3 = last_values[3][0];
-        let nplus2 = last_values[2][0];
+impl<SYS: OdeSystem, S: StateVector<Scalar = f64>> KStepMethodStep<SYS, S> for MilneSimpsonHack {
+    fn step(
+        &self,
+        _system: &SYS,
+        t: f64,
+        last_values: &[S],
+        _last_slopes: &[S],
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> S {
+        // The right-hand side is evaluated inline, once per value used
+        stats.rhs_evaluations += 2;
+        let nplus3 = last_values[3].as_slice()[0];
+        let nplus2 = last_values[2].as_slice()[0];
 
         let tplus4 = t + h;
         let tplus3 = t;
         let tplus2 = t - h;
 
-        vec![
-            (nplus2 - (h / 3.0) * (tplus2 * tplus2 * nplus2 + 4.0 * tplus3 * tplus3 * nplus3))
-                / (1.0 + h * tplus4 * tplus4 / 3.0),
-        ]
+        // Only the first component is used
+        let mut next_values = last_values[0].zeros_like();
+        next_values.as_mut_slice()[0] = (nplus2
+            - (h / 3.0) * (tplus2 * tplus2 * nplus2 + 4.0 * tplus3 * tplus3 * nplus3))
+            / (1.0 + h * tplus4 * tplus4 / 3.0);
+        next_values
+    }
+
+    fn uses_slopes(&self) -> bool {
+        false
+    }
+}
+
+impl MethodInfo for MilneSimpsonHack {
+    fn name(&self) -> &str {
+        "Milne-Simpson (hack)"
+    }
+
+    fn order(&self) -> usize {
+        4
+    }
+
+    fn stages(&self) -> usize {
+        2
+    }
+
+    fn steps(&self) -> usize {
+        4
+    }
+
+    /// Implicit, the equation is only solved by hand for the problem of the task
+    fn is_explicit(&self) -> bool {
+        false
     }
 }
 
 /// Makes a system of ODEs into a sampleable function using an Adams-Bashforth method.
 pub fn make_milne_simpson_hack_method<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
-    StartStep: OneStepMethodStep<FT>,
+    SYS: OdeSystem,
+    S: StateVector<Scalar = f64>,
+    StartStep: OneStepMethodStep<SYS, S>,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS, S> + 'static,
+    StartGen: Fn(InitialValueSystemProblem<SYS, S>, f64) -> OneStepMethod<SYS, StartStep, S> + 'static,
 >(
-    ivp: fn() -> InitialValueSystemProblem<FT>,
+    ivp: IvpGen,
     h: f64,
-    start_method_gen: fn(
-        ivp: InitialValueSystemProblem<FT>,
-        h: f64,
-    ) -> OneStepMethod<FT, StartStep>,
-) -> KStepMethod<FT, MilneSimpsonHack, StartStep> {
-    KStepMethod::new(ivp, h, 4, MilneSimpsonHack, start_method_gen)
+    start_method_gen: StartGen,
+) -> KStepMethod<SYS, MilneSimpsonHack, StartStep, S> {
+    KStepMethod::new(
+        Box::new(ivp),
+        h,
+        4,
+        MilneSimpsonHack,
+        Box::new(start_method_gen),
+    )
 }
 
 /// Task 10, 3 Method, takes alpha
 #[allow(non_camel_case_types)]
 pub struct Task_10_3_Method(f64);
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> KStepMethodStep<FT> for Task_10_3_Method {
-    fn step(&self, _k: usize, _dfs: &[FT], t: f64, last_values: &[Vec<f64>], h: f64) -> Vec<f64> {
-        let xplus1 = last_values[1][0];
-        let x = last_values[0][0];
+impl<SYS: OdeSystem, S: StateVector<Scalar = f64>> KStepMethodStep<SYS, S> for Task_10_3_Method {
+    fn step(
+        &self,
+        _system: &SYS,
+        t: f64,
+        last_values: &[S],
+        _last_slopes: &[S],
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> S {
+        // The right-hand side is evaluated inline, once per value used
+        stats.rhs_evaluations += 3;
+        let xplus1 = last_values[1].as_slice()[0];
+        let x = last_values[0].as_slice()[0];
 
         let tplus2 = t + h;
         let tplus1 = t;
@@ -96,29 +197,41 @@ impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> KStepMethodStep<FT> for Task_
         let b_1 = -2.0 * self.0 / 3.0 + 2.0 / 3.0;
         let b_0 = -5.0 * self.0 / 12.0 - 1.0 / 12.0;
 
-        vec![
-            (h * (b_2 * exp!(-tplus2) * cos!(tplus2)
+        // Only the first component is used
+        let mut next_values = last_values[0].zeros_like();
+        next_values.as_mut_slice()[0] = (h
+            * (b_2 * exp!(-tplus2) * cos!(tplus2)
                 + b_1 * (-xplus1 + exp!(-tplus1) * cos!(tplus1))
                 + b_0 * (-x + exp!(-tplus0) * cos!(tplus0)))
-                - a_1 * xplus1
-                - a_0 * x)
-                / (1.0 + h * b_2),
-        ]
+            - a_1 * xplus1
+            - a_0 * x)
+            / (1.0 + h * b_2);
+        next_values
+    }
+
+    fn uses_slopes(&self) -> bool {
+        false
     }
 }
 
 /// Makes a system of ODEs into a sampleable function.
 pub fn make_task_10_3_method<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
-    StartStep: OneStepMethodStep<FT>,
+    SYS: OdeSystem,
+    S: StateVector<Scalar = f64>,
+    StartStep: OneStepMethodStep<SYS, S>,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS, S> + 'static,
+    StartGen: Fn(InitialValueSystemProblem<SYS, S>, f64) -> OneStepMethod<SYS, StartStep, S> + 'static,
 >(
-    ivp: fn() -> InitialValueSystemProblem<FT>,
+    ivp: IvpGen,
     h: f64,
-    start_method_gen: fn(
-        ivp: InitialValueSystemProblem<FT>,
-        h: f64,
-    ) -> OneStepMethod<FT, StartStep>,
+    start_method_gen: StartGen,
     a: f64,
-) -> KStepMethod<FT, Task_10_3_Method, StartStep> {
-    KStepMethod::new(ivp, h, 2, Task_10_3_Method(a), start_method_gen)
+) -> KStepMethod<SYS, Task_10_3_Method, StartStep, S> {
+    KStepMethod::new(
+        Box::new(ivp),
+        h,
+        2,
+        Task_10_3_Method(a),
+        Box::new(start_method_gen),
+    )
 }
@@ -1,7 +1,12 @@
 use crate::definitions::{
-    ClosureDifferentiableFunction, InitialValueProblem, Point2D, SimpleDifferentiableFunction,
+    InitialValueProblem, InitialValueSystemProblem, ODEMethod, OdeSystem, Point2D,
+    SimpleDifferentiableFunction, SolverError, StateVector,
 };
-use crate::newton_method::newton_method;
+use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
+use crate::method_info::MethodInfo;
+use crate::newton_method::newton_method_system;
+use crate::stats::SolverStats;
+use num::complex::Complex64;
 use rayon::prelude::*;
 
 /// Implementation of the implicit euler method.
@@ -35,14 +40,15 @@ pub fn implicit_euler(
     ivp: InitialValueProblem<SimpleDifferentiableFunction<(f64, f64)>>,
     h: f64,
     t_target: f64,
-) -> f64 {
-    implicit_euler_interval(ivp, h, t_target, 0)
+) -> Result<f64, SolverError> {
+    // There always is at least the start value
+    Ok(implicit_euler_interval(ivp, h, t_target, 0)?
         .last()
         .unwrap()
-        .y
+        .y)
 }
 
-/// Implementation of the explicit euler method.
+/// Implementation of the implicit euler method.
 /// Lands on target even if h does not match.
 /// The function returns the intermediate values as well as the final value in the form of a point vector
 ///
@@ -50,59 +56,149 @@ pub fn implicit_euler(
 ///
 /// * `ivp` - The initial value problem we want to approximate
 /// * `h` - Step size of the algorithm
-/// * `t_target` - Target time we want to get the value for. Note that this should be directly reachable with t0 + k * h
+/// * `t_target` - Target time we want to get the value for. Note that this should be directly reachable with t0 + k * h.
+///   Lies before t0 to integrate backwards in time.
 /// * `skip_n` - If > 0 only returns ever n-th value to reduce memory footprint while retaining smaller h
 ///
+/// Piggy-backs on the multi-dimensional implementation, which also reports the stats of Newton's method.
 pub fn implicit_euler_interval(
     ivp: InitialValueProblem<SimpleDifferentiableFunction<(f64, f64)>>,
     h: f64,
     t_target: f64,
     skip_n: isize,
-) -> Vec<Point2D> {
-    let mut skip = skip_n;
-    let mut t = ivp.start_time;
-    let mut val = ivp.start_value;
-    let mut vals = Vec::with_capacity(((t_target - ivp.start_time) / h).ceil() as usize);
-    vals.push(Point2D { x: t, y: val });
-
-    while t + h < t_target {
-        val = implicit_euler_step(ivp.df, t, val, h);
-        t += h;
-        skip -= 1;
-        if skip <= 0 {
-            vals.push(Point2D { x: t, y: val });
-            //dbg!(Point2D { x: t, y: val });
-            skip = skip_n;
-        }
+) -> Result<Vec<Point2D>, SolverError> {
+    let problem = InitialValueSystemProblem::new(
+        ivp.start_time,
+        vec![ivp.start_value],
+        ScalarWithJacobian(ivp.df),
+    );
+    Ok(make_implicit_euler_method_system(problem, h)
+        .interval(t_target, skip_n)?
+        .component_points(0))
+}
+
+/// Scalar right-hand side whose derivative is ∂f/∂y, so Newton's method can use it.
+#[derive(Copy, Clone)]
+struct ScalarWithJacobian(SimpleDifferentiableFunction<(f64, f64)>);
+
+impl OdeSystem for ScalarWithJacobian {
+    fn rhs(&self, t: f64, y: &[f64], dydt: &mut [f64]) {
+        dydt[0] = (self.0.f)((t, y[0]));
+    }
+
+    fn dimension(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn jacobian(&self, t: f64, y: &[f64], jac: &mut [f64]) {
+        jac[0] = (self.0.df)((t, y[0]));
+    }
+
+    fn jacobian_rhs_evaluations(&self, _dim: usize) -> usize {
+        0
+    }
+}
+
+/// Step of the implicit euler method for arbitrary dimensions.
+/// Solves y_n+1 = y_n + h f(t_n+1, y_n+1) with Newton's method, using the Jacobian of the system.
+pub struct ImplicitEulerStep;
+
+impl<SYS: OdeSystem, S: StateVector<Scalar = f64>> OneStepMethodStep<SYS, S> for ImplicitEulerStep {
+    fn step(
+        &self,
+        system: &SYS,
+        t: f64,
+        last_values: &S,
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> Result<S, SolverError> {
+        let dim = last_values.dim();
+        let y = last_values.as_slice();
+        let mut next_values = last_values.clone();
+        let jacobians_before = stats.jacobian_evaluations;
+        let result = newton_method_system(
+            // G(x) = y + h f(t + h, x) - x
+            |x: &[f64], g: &mut [f64]| {
+                system.rhs(t + h, x, g);
+                for ((g_i, y_i), x_i) in g.iter_mut().zip(y).zip(x) {
+                    *g_i = y_i + h * *g_i - x_i;
+                }
+            },
+            // G'(x) = h J - I
+            |x: &[f64], dg: &mut [f64]| {
+                system.jacobian(t + h, x, dg);
+                for (idx, dg_ij) in dg.iter_mut().enumerate() {
+                    *dg_ij = h * *dg_ij - if idx % (dim + 1) == 0 { 1.0 } else { 0.0 };
+                }
+            },
+            t,
+            next_values.as_mut_slice(),
+            0.001 * h.abs(),
+            stats,
+        );
+        // Approximating the Jacobian costs evaluations of f as well
+        stats.rhs_evaluations +=
+            (stats.jacobian_evaluations - jacobians_before) * system.jacobian_rhs_evaluations(dim);
+        result?;
+        Ok(next_values)
+    }
+}
+
+impl MethodInfo for ImplicitEulerStep {
+    fn name(&self) -> &str {
+        "Implicit Euler"
+    }
+
+    fn order(&self) -> usize {
+        1
     }
 
-    val = implicit_euler_step(ivp.df, t, val, t_target - t);
-    vals.push(Point2D {
-        x: t_target,
-        y: val,
-    });
-    vals
+    fn stages(&self) -> usize {
+        1
+    }
+
+    fn is_explicit(&self) -> bool {
+        false
+    }
+
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        Some(1.0 / (1.0 - z))
+    }
 }
 
-fn implicit_euler_step(
-    df: SimpleDifferentiableFunction<(f64, f64)>,
-    t: f64,
-    val: f64,
+/// Makes a system of ODEs into a sampleable function using the implicit euler method.
+/// The stats of the solutions include the work done by Newton's method.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, ODEMethod};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+/// use ngdl_rust::implicit_euler::make_implicit_euler_method_system;
+/// use ngdl_rust::modified_explicit_euler::make_modified_explicit_euler_method_system;
+///
+/// // Stiff system, the second component decays much faster than the first
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+///     dydt[0] = -y[0];
+///     dydt[1] = -1000.0 * (y[1] - y[0]);
+/// };
+/// let problem = || InitialValueSystemProblem::new(0.0, vec![1.0, 0.0], rhs);
+/// let h = 0.01;
+///
+/// let implicit = make_implicit_euler_method_system(problem(), h).interval(1.0, 0).unwrap();
+/// let modified = make_modified_explicit_euler_method_system(problem(), h).interval(1.0, 0).unwrap();
+/// let rk4 = make_classic_runge_kutta(problem(), h).interval(1.0, 0).unwrap();
+///
+/// // hλ = -10 lies far outside of the stability regions of the explicit methods
+/// assert!((implicit.final_state().unwrap()[1] - (-1.0f64).exp()).abs() < 0.01);
+/// assert!(modified.final_state().unwrap()[1].abs() > 1e10);
+/// assert!(rk4.final_state().unwrap()[1].abs() > 1e10);
+/// assert!(implicit.stats().newton_iterations > 0);
+/// ```
+pub fn make_implicit_euler_method_system<SYS: OdeSystem, S: StateVector<Scalar = f64>>(
+    ivp: InitialValueSystemProblem<SYS, S>,
     h: f64,
-) -> f64 {
-    let to_solve =
-        |(t, x), (last, delta, g): (f64, f64, SimpleDifferentiableFunction<(f64, f64)>)| {
-            last + delta * (g.f)((t + delta, x)) - x
-        };
-    let to_solve_derivative =
-        |(t, x), (_, delta, g): (f64, f64, SimpleDifferentiableFunction<(f64, f64)>)| {
-            delta * (g.df)((t + delta, x)) - 1.0
-        };
-    let func: ClosureDifferentiableFunction<
-        (f64, f64),
-        (f64, f64, SimpleDifferentiableFunction<(f64, f64)>),
-    > = ClosureDifferentiableFunction::new((val, h, df), to_solve, to_solve_derivative);
-    newton_method(func, t, val, 0.001 * h)
+) -> OneStepMethod<SYS, ImplicitEulerStep, S> {
+    OneStepMethod::new(ImplicitEulerStep, ivp, h)
 }
 
 /// Runs the implicit euler method (interval version) for all supplied h in parallel.
@@ -111,8 +207,31 @@ pub fn implicit_euler_interval_test_run(
     h: &[f64],
     t_target: f64,
     skip_n: isize,
-) -> Vec<Vec<Point2D>> {
+) -> Result<Vec<Vec<Point2D>>, SolverError> {
     h.par_iter()
         .map(|h| implicit_euler_interval(ivp, *h, t_target, skip_n))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::ODEMethod;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_counts_rhs_of_jacobian() {
+        let calls = Cell::new(0);
+        let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+            calls.set(calls.get() + 1);
+            dydt[0] = -y[0];
+            dydt[1] = -1000.0 * (y[1] - y[0]);
+        };
+        let problem = InitialValueSystemProblem::new(0.0, vec![1.0, 0.0], rhs);
+
+        let solution = make_implicit_euler_method_system(problem, 0.01)
+            .interval(1.0, 0)
+            .unwrap();
+        assert_eq!(solution.stats().rhs_evaluations, calls.get());
+    }
+}
@@ -7,8 +7,12 @@ pub mod adams_bashforth;
 mod constants;
 /// Contains generic definitions and helper methods
 pub mod definitions;
+/// Continuous extensions to sample solutions between the grid points
+pub mod dense_output;
 /// Implementation of the explicit euler method
 pub mod euler_explicit;
+/// Detection of zero crossings of event functions during the integration
+pub mod events;
 /// Basic implementation of an explicit Runge-Kutta method.
 pub mod explicit_runge_kutta;
 /// Implementation of the finite differences method
@@ -19,19 +23,40 @@ mod generalized_explicit_one_step_method;
 pub mod hack;
 /// Implementation of the implicit euler method
 pub mod implicit_euler;
+/// Metadata of the methods like name and order
+pub mod method_info;
 /// Implementation of the Milne Simpson predictor-corrector method.
 pub mod milne_simpson;
 /// Explicit euler also using the derivative of the given DGL.
 pub mod modified_explicit_euler;
-mod newton_method;
+/// Newton's method for scalar equations and systems
+pub mod newton_method;
 /// Explicit Nyström method
 pub mod nystroem;
+/// Callbacks to follow or cancel the solvers while they step
+pub mod observer;
 /// Little plot helpers to reduce boilerplate
 pub mod plot_util;
 /// Numeric quadrature with several methods
 pub mod quadrature;
+/// Looking up methods by name
+pub mod registry;
+/// Streaming the states of a solver to files while it steps
+pub mod sink;
+/// The result type of the ODE solvers
+pub mod solution;
+/// A single entry point for all ODE solvers, configured by options
+pub mod solve;
 /// Functions to sample stability functions to get stability areas.
 pub mod stability_area;
+/// Counting the work done by the solvers
+pub mod stats;
+/// Strategies to choose the step sizes of adaptive methods
+pub mod step_size_controller;
+/// Step by step integration as an iterator
+pub mod stepper;
+/// Tolerances and error norms of adaptive methods
+pub mod tolerance;
 /// Helpful helpers for common computations
 pub mod util;
 
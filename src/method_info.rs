@@ -0,0 +1,44 @@
+use num::complex::Complex64;
+
+/// Describes a method, e.g. to label plots or to compare the observed with the nominal order.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::explicit_runge_kutta::Tableau;
+/// use ngdl_rust::method_info::MethodInfo;
+/// use num::complex::Complex64;
+///
+/// let rk4 = Tableau::classic();
+/// assert_eq!(rk4.name(), "Classic Runge-Kutta");
+/// assert_eq!(rk4.order(), 4);
+/// assert_eq!(rk4.stages(), 4);
+///
+/// // R(z) = 1 + z + z^2/2 + z^3/6 + z^4/24
+/// let r = rk4.stability_function(Complex64::new(-1.0, 0.0)).unwrap();
+/// assert!((r.re - 0.375).abs() < 1e-12);
+/// ```
+pub trait MethodInfo {
+    /// Human readable name, e.g. for captions.
+    fn name(&self) -> &str;
+
+    /// Nominal order of convergence.
+    fn order(&self) -> usize;
+
+    /// Evaluations of the right-hand side per step.
+    fn stages(&self) -> usize;
+
+    /// Number of previous values a step uses, 1 for one step methods.
+    fn steps(&self) -> usize {
+        1
+    }
+
+    /// Whether a step can be computed without solving an equation.
+    fn is_explicit(&self) -> bool {
+        true
+    }
+
+    /// R(z) of the test equation y' = λy with z = hλ, None if the method has none or it is not known.
+    fn stability_function(&self, _z: Complex64) -> Option<Complex64> {
+        None
+    }
+}
@@ -1,72 +1,91 @@
-use crate::definitions::{InitialValueSystemProblem, PointwiseAdd, SampleableFunction, ScalarMul};
+use crate::definitions::{InitialValueSystemProblem, OdeSystem, Scalar, StateVector};
 use crate::generalized_explicit_k_step_method::{KStepMethod, KStepMethodStep};
 use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
+use crate::method_info::MethodInfo;
+use crate::stats::SolverStats;
 
 /// Implementation of the Milne Simpson predictor-corrector method.
 pub struct MilneSimpson;
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> KStepMethodStep<FT> for MilneSimpson {
-    fn step(&self, _k: usize, dfs: &[FT], t: f64, last_values: &[Vec<f64>], h: f64) -> Vec<f64> {
+impl<SYS: OdeSystem<S::Scalar>, S: StateVector> KStepMethodStep<SYS, S> for MilneSimpson {
+    fn step(
+        &self,
+        system: &SYS,
+        t: f64,
+        last_values: &[S],
+        last_slopes: &[S],
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> S {
         // Predictor
-        let p = predict(dfs, t, last_values, h);
-        // Corrector
-        correct(dfs, t, last_values, h, p)
+        let p = predict(last_values, last_slopes, h);
+        // Corrector, f at the predicted value is the only new evaluation
+        stats.rhs_evaluations += 1;
+        let f_predicted = system.rhs_state(t + h, &p);
+        correct(last_values, last_slopes, h, &f_predicted)
     }
 }
 
-fn correct<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    dfs: &[FT],
-    t: f64,
-    last_values: &[Vec<f64>],
-    h: f64,
-    predictor: Vec<f64>,
-) -> Vec<f64> {
-    let nplus3: Vec<f64> = last_values[3].clone();
-    let nplus2: Vec<f64> = last_values[2].clone();
+impl MethodInfo for MilneSimpson {
+    fn name(&self) -> &str {
+        "Milne-Simpson"
+    }
+
+    fn order(&self) -> usize {
+        4
+    }
+
+    /// f at the predicted and at the corrected value
+    fn stages(&self) -> usize {
+        2
+    }
 
-    dfs.iter()
-        .map(|df| {
-            df.value_at((t - h, nplus2.clone()))
-                + 4.0 * df.value_at((t, nplus3.clone()))
-                + df.value_at((t + h, predictor.clone()))
-        })
-        .collect::<Vec<f64>>()
-        .scalar_mul(h / 3.0)
-        .pointwise_add(nplus2)
+    fn steps(&self) -> usize {
+        4
+    }
 }
 
-fn predict<FT: SampleableFunction<(f64, Vec<f64>), f64>>(
-    dfs: &[FT],
-    t: f64,
-    last_values: &[Vec<f64>],
-    h: f64,
-) -> Vec<f64> {
-    let nplus3: Vec<f64> = last_values[3].clone();
-    let nplus2: Vec<f64> = last_values[2].clone();
-    let nplus1: Vec<f64> = last_values[1].clone();
-    let n: Vec<f64> = last_values[0].clone();
+fn correct<S: StateVector>(last_values: &[S], last_slopes: &[S], h: f64, f_predicted: &S) -> S {
+    let nplus2 = &last_values[2];
+
+    let h_third = S::Scalar::from_ratio(h, 3.0);
 
-    dfs.iter()
-        .map(|df| {
-            2.0 * df.value_at((t - 2.0 * h, nplus1.clone())) - df.value_at((t - h, nplus2.clone()))
-                + 2.0 * df.value_at((t, nplus3.clone()))
-        })
-        .collect::<Vec<f64>>()
-        .scalar_mul(4.0 * h / 3.0)
-        .pointwise_add(n)
+    let mut corrected = nplus2.clone();
+    corrected.axpy(h_third, &last_slopes[2]);
+    corrected.axpy(S::Scalar::from_f64(4.0) * h_third, &last_slopes[3]);
+    corrected.axpy(h_third, f_predicted);
+    corrected
+}
+
+fn predict<S: StateVector>(last_values: &[S], last_slopes: &[S], h: f64) -> S {
+    let n = &last_values[0];
+
+    let h_third = S::Scalar::from_ratio(h, 3.0);
+
+    let mut predicted = n.clone();
+    predicted.axpy(S::Scalar::from_f64(8.0) * h_third, &last_slopes[1]);
+    predicted.axpy(S::Scalar::from_f64(-4.0) * h_third, &last_slopes[2]);
+    predicted.axpy(S::Scalar::from_f64(8.0) * h_third, &last_slopes[3]);
+    predicted
 }
 
 /// Makes a system of ODEs into a sampleable function using an method.
 pub fn make_milne_simpson_method<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
-    StartStep: OneStepMethodStep<FT>,
+    SYS: OdeSystem<S::Scalar>,
+    S: StateVector,
+    StartStep: OneStepMethodStep<SYS, S>,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS, S> + 'static,
+    StartGen: Fn(InitialValueSystemProblem<SYS, S>, f64) -> OneStepMethod<SYS, StartStep, S> + 'static,
 >(
-    ivp: fn() -> InitialValueSystemProblem<FT>,
+    ivp: IvpGen,
     h: f64,
-    start_method_gen: fn(
-        ivp: InitialValueSystemProblem<FT>,
-        h: f64,
-    ) -> OneStepMethod<FT, StartStep>,
-) -> KStepMethod<FT, MilneSimpson, StartStep> {
-    KStepMethod::new(ivp, h, 4, MilneSimpson, start_method_gen)
+    start_method_gen: StartGen,
+) -> KStepMethod<SYS, MilneSimpson, StartStep, S> {
+    KStepMethod::new(
+        Box::new(ivp),
+        h,
+        4,
+        MilneSimpson,
+        Box::new(start_method_gen),
+    )
 }
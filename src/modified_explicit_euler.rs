@@ -1,54 +1,55 @@
-use crate::definitions::{DifferentiableFunction, InitialValueProblem, Point2D};
-
-/// Simple implementation of the step taken during the explicit euler function.
-fn modified_explicit_euler_step<FT: DifferentiableFunction<(f64, f64), f64>>(
-    df: &FT,
-    t: f64,
-    last_value: f64,
-    h: f64,
-) -> f64 {
-    last_value
-        + h * df.value_at((t, last_value))
-        + (h * h / 2.0) * df.derivative_at((t, last_value))
-}
+use crate::definitions::{
+    DifferentiableFunction, InitialValueProblem, InitialValueSystemProblem, ODEMethod, OdeSystem,
+    Point2D, Scalar, SolverError, StateVector,
+};
+use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
+use crate::method_info::MethodInfo;
+use crate::stats::SolverStats;
+use num::complex::Complex64;
 
 /// Modified implementation of the explicit euler method using y'' as well.
 /// Lands on target even if h does not match.
 ///
 /// # Arguments
 ///
-/// * `ivp` - The initial value problem we want to approximate
+/// * `ivp` - The initial value problem we want to approximate, derivative_at gives y''
 /// * `h` - Step size of the algorithm
 /// * `t_target` - Target time we want to get the value for.
 ///
 /// # Example
 /// ```
-/// use ngdl_rust::definitions::{InitialValueProblem, Function2D};
-/// use ngdl_rust::euler_explicit::explicit_euler;
+/// use ngdl_rust::definitions::{InitialValueProblem, SimpleDifferentiableFunction2D, SimpleDifferentiableFunction};
+/// use ngdl_rust::modified_explicit_euler::modified_explicit_euler;
 ///
-/// let ivp: InitialValueProblem<Function2D> = InitialValueProblem::new(0.0, 1.0, |(_, x)| x*x);
-/// dbg!(explicit_euler(ivp, 0.001, 1.0));
+/// let ivp: InitialValueProblem<SimpleDifferentiableFunction2D> = InitialValueProblem::new(
+///     0.0,
+///     1.0,
+///     SimpleDifferentiableFunction::new(|(_, x)| x, |(_, x)| x),
+/// );
+/// assert!((modified_explicit_euler(ivp, 0.001, 1.0).unwrap() - 1f64.exp()).abs() < 1e-5);
 /// ```
 pub fn modified_explicit_euler<FT: DifferentiableFunction<(f64, f64), f64>>(
     ivp: InitialValueProblem<FT>,
     h: f64,
     t_target: f64,
-) -> f64 {
-    modified_explicit_euler_interval(ivp, h, t_target, 0)
+) -> Result<f64, SolverError> {
+    // There always is at least the start value
+    Ok(modified_explicit_euler_interval(ivp, h, t_target, 0)?
         .last()
         .unwrap()
-        .y
+        .y)
 }
 
 /// Modified implementation of the explicit euler method using y'' as well.
 /// Lands on target even if h does not match.
 /// The function returns the intermediate values as well as the final value in the form of a point vector
+/// Piggy-backs on the multi-dimensional implementation.
 ///
 /// # Arguments
 ///
-/// * `ivp` - The initial value problem we want to approximate
+/// * `ivp` - The initial value problem we want to approximate, derivative_at gives y''
 /// * `h` - Step size of the algorithm
-/// * `t_target` - Target time we want to get the value for.
+/// * `t_target` - Target time we want to get the value for, lies before t0 to integrate backwards in time.
 /// * `skip_n` - If > 0 only returns ever n-th value to reduce memory footprint while retaining smaller h
 ///
 pub fn modified_explicit_euler_interval<FT: DifferentiableFunction<(f64, f64), f64>>(
@@ -56,27 +57,133 @@ pub fn modified_explicit_euler_interval<FT: DifferentiableFunction<(f64, f64), f
     h: f64,
     t_target: f64,
     skip_n: isize,
-) -> Vec<Point2D> {
-    let mut skip: isize = skip_n;
-    let mut t = ivp.start_time;
-    let mut val = ivp.start_value;
-    let mut vals = Vec::with_capacity(((t_target - ivp.start_time) / h).ceil() as usize);
-    vals.push(Point2D { x: t, y: val });
-
-    while t + h < t_target {
-        val = modified_explicit_euler_step(&ivp.df, t, val, h);
-        t += h;
-        skip -= 1;
-        if skip <= 0 {
-            vals.push(Point2D { x: t, y: val });
-            skip = skip_n
-        }
+) -> Result<Vec<Point2D>, SolverError> {
+    let problem = InitialValueSystemProblem::new(
+        ivp.start_time,
+        vec![ivp.start_value],
+        ScalarWithSecondDerivative(ivp.df),
+    );
+    Ok(make_modified_explicit_euler_method_system(problem, h)
+        .interval(t_target, skip_n)?
+        .component_points(0))
+}
+
+/// Scalar right-hand side whose derivative is the total derivative y''.
+struct ScalarWithSecondDerivative<FT>(FT);
+
+impl<FT: DifferentiableFunction<(f64, f64), f64>> OdeSystem for ScalarWithSecondDerivative<FT> {
+    fn rhs(&self, t: f64, y: &[f64], dydt: &mut [f64]) {
+        dydt[0] = self.0.value_at((t, y[0]));
     }
 
-    val = modified_explicit_euler_step(&ivp.df, t, val, t_target - t);
-    vals.push(Point2D {
-        x: t_target,
-        y: val,
-    });
-    vals
+    fn dimension(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn second_derivative(&self, t: f64, y: &[f64], d2ydt2: &mut [f64]) {
+        d2ydt2[0] = self.0.derivative_at((t, y[0]));
+    }
+
+    fn second_derivative_evaluations(&self, _dim: usize) -> (usize, usize) {
+        (0, 0)
+    }
+}
+
+/// Step of the modified explicit euler method for arbitrary dimensions.
+/// Takes the Taylor polynomial y + h y' + h^2/2 y'', with y'' from `OdeSystem::second_derivative`.
+pub struct ModifiedExplicitEulerStep;
+
+impl<SYS: OdeSystem<S::Scalar>, S: StateVector> OneStepMethodStep<SYS, S>
+    for ModifiedExplicitEulerStep
+{
+    fn step(
+        &self,
+        system: &SYS,
+        t: f64,
+        last_values: &S,
+        h: f64,
+        stats: &mut SolverStats,
+    ) -> Result<S, SolverError> {
+        let mut slope = last_values.zeros_like();
+        system.rhs(t, last_values.as_slice(), slope.as_mut_slice());
+        stats.rhs_evaluations += 1;
+        let mut curvature = last_values.zeros_like();
+        system.second_derivative(t, last_values.as_slice(), curvature.as_mut_slice());
+        // y'' needs f and its derivatives unless the system knows it
+        let (rhs_evaluations, jacobian_evaluations) =
+            system.second_derivative_evaluations(last_values.dim());
+        stats.rhs_evaluations += rhs_evaluations;
+        stats.jacobian_evaluations += jacobian_evaluations;
+
+        let mut next_values = last_values.clone();
+        next_values.axpy(S::Scalar::from_f64(h), &slope);
+        next_values.axpy(S::Scalar::from_f64(h * h / 2.0), &curvature);
+        Ok(next_values)
+    }
+}
+
+impl MethodInfo for ModifiedExplicitEulerStep {
+    fn name(&self) -> &str {
+        "Modified Explicit Euler"
+    }
+
+    fn order(&self) -> usize {
+        2
+    }
+
+    fn stages(&self) -> usize {
+        1
+    }
+
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        Some(1.0 + z + z * z / 2.0)
+    }
+}
+
+/// Makes a system of ODEs into a sampleable function using the modified explicit euler method.
+pub fn make_modified_explicit_euler_method_system<SYS: OdeSystem<S::Scalar>, S: StateVector>(
+    ivp: InitialValueSystemProblem<SYS, S>,
+    h: f64,
+) -> OneStepMethod<SYS, ModifiedExplicitEulerStep, S> {
+    OneStepMethod::new(ModifiedExplicitEulerStep, ivp, h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::{ODEMethod, SimpleDifferentiableFunction};
+    use std::cell::Cell;
+
+    #[test]
+    fn test_counts_finite_differences() {
+        let calls = Cell::new(0);
+        let rhs = |t: f64, y: &[f64], dydt: &mut [f64]| {
+            calls.set(calls.get() + 1);
+            dydt[0] = y[1] * t;
+            dydt[1] = -y[0];
+        };
+        let problem = InitialValueSystemProblem::new(0.0, vec![1.0, 0.0], rhs);
+
+        let solution = make_modified_explicit_euler_method_system(problem, 0.1)
+            .interval(1.0, 0)
+            .unwrap();
+        let stats = solution.stats();
+        assert_eq!(stats.rhs_evaluations, calls.get());
+        assert_eq!(stats.jacobian_evaluations, stats.accepted_steps);
+    }
+
+    #[test]
+    fn test_known_second_derivative() {
+        // y' = -y, y'' = y
+        let df = SimpleDifferentiableFunction::new(|(_, y): (f64, f64)| -y, |(_, y)| y);
+        let problem =
+            InitialValueSystemProblem::new(0.0, vec![1.0], ScalarWithSecondDerivative(df));
+
+        let solution = make_modified_explicit_euler_method_system(problem, 0.1)
+            .interval(1.0, 0)
+            .unwrap();
+        let stats = solution.stats();
+        assert_eq!(stats.rhs_evaluations, stats.accepted_steps);
+        assert_eq!(stats.jacobian_evaluations, 0);
+    }
 }
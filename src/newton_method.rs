@@ -1,26 +1,86 @@
 use crate::abs;
-use crate::definitions::{DifferentiableFunction, SampleableFunction};
+use crate::definitions::{DifferentiableFunction, SolverError};
+use crate::stats::SolverStats;
+use nalgebra::{DMatrix, DVector};
 
+/// Iterations after which Newton's method is considered to not converge.
+const MAX_ITERATIONS: usize = 100;
+
+/// Newton's method for func(t, x) = 0 in x, starting at start_x. Converged once |func| is at most eps.
+/// Counts every evaluation of func as one of the right-hand side and every derivative as a Jacobian.
 pub fn newton_method<F: DifferentiableFunction<(f64, f64), f64>>(
     func: F,
     t: f64,
     start_x: f64,
     eps: f64,
-) -> f64 {
-    // Don't do work if our guess is good enough already...
-    if abs!(func.value_at((t, start_x))) < eps {
-        return start_x;
-    }
+    stats: &mut SolverStats,
+) -> Result<f64, SolverError> {
+    let mut current = start_x;
+    let mut iterations = 0;
 
-    let mut last = start_x;
-    let mut current = newton_step(&func, t, last);
+    loop {
+        let residual = abs!(func.value_at((t, current)));
+        stats.rhs_evaluations += 1;
+        // Don't do work if our guess is good enough already...
+        if residual <= eps {
+            return Ok(current);
+        }
+        // Diverged (or a zero derivative sent us to infinity) or just too slow
+        if iterations == MAX_ITERATIONS || !residual.is_finite() {
+            return Err(SolverError::NewtonNotConverged { t, iterations });
+        }
 
-    while abs!(func.value_at((t, current))) > eps {
-        last = current;
-        current = newton_step(&func, t, last);
+        current = newton_step(&func, t, current);
+        // The step evaluates func and its derivative once more
+        stats.rhs_evaluations += 1;
+        stats.jacobian_evaluations += 1;
+        iterations += 1;
+        stats.newton_iterations += 1;
     }
+}
+
+/// Newton's method for a system G(x) = 0 with n equations, improves x in place.
+/// `residual` writes G(x), `jacobian` writes G'(x) row by row, they are counted as evaluations
+/// of the right-hand side and of the Jacobian respectively.
+/// Converged once every component of G(x) is at most eps.
+pub fn newton_method_system<G: Fn(&[f64], &mut [f64]), DG: Fn(&[f64], &mut [f64])>(
+    residual: G,
+    jacobian: DG,
+    t: f64,
+    x: &mut [f64],
+    eps: f64,
+    stats: &mut SolverStats,
+) -> Result<(), SolverError> {
+    let dim = x.len();
+    let mut g = vec![0.0; dim];
+    let mut dg = vec![0.0; dim * dim];
+    let mut iterations = 0;
 
-    current
+    loop {
+        residual(x, &mut g);
+        stats.rhs_evaluations += 1;
+        let residual_max = g.iter().fold(0.0, |max: f64, g_i| max.max(abs!(g_i)));
+        if residual_max <= eps {
+            return Ok(());
+        }
+        if iterations == MAX_ITERATIONS || !residual_max.is_finite() {
+            return Err(SolverError::NewtonNotConverged { t, iterations });
+        }
+
+        jacobian(x, &mut dg);
+        stats.jacobian_evaluations += 1;
+        // Solve G'(x) dx = -G(x)
+        let lu = DMatrix::from_row_slice(dim, dim, &dg).lu();
+        stats.lu_decompositions += 1;
+        let dx = lu
+            .solve(&DVector::from_iterator(dim, g.iter().map(|g_i| -g_i)))
+            .ok_or(SolverError::SingularMatrix)?;
+        for (x_i, dx_i) in x.iter_mut().zip(dx.iter()) {
+            *x_i += dx_i;
+        }
+        iterations += 1;
+        stats.newton_iterations += 1;
+    }
 }
 
 fn newton_step<F: DifferentiableFunction<(f64, f64), f64>>(func: &F, t: f64, val: f64) -> f64 {
@@ -30,14 +90,44 @@ fn newton_step<F: DifferentiableFunction<(f64, f64), f64>>(func: &F, t: f64, val
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::definitions::SimpleDifferentiableFunction;
+    use crate::definitions::{SampleableFunction, SimpleDifferentiableFunction};
 
     #[test]
     fn test_simple() {
         let func = SimpleDifferentiableFunction::new(|(_, x)| x * x, |(_, x)| 2.0 * x);
         let eps = 0.001;
 
-        let func_val = func.value_at((0.0, newton_method(func, 0.0, 2.0, eps)));
+        let func_val = func.value_at((
+            0.0,
+            newton_method(func, 0.0, 2.0, eps, &mut SolverStats::default()).unwrap(),
+        ));
         assert!(eps > abs!(func_val));
     }
+
+    #[test]
+    fn test_system() {
+        // x^2 = 4, x * y = 6
+        let residual = |x: &[f64], g: &mut [f64]| {
+            g[0] = x[0] * x[0] - 4.0;
+            g[1] = x[0] * x[1] - 6.0;
+        };
+        let jacobian =
+            |x: &[f64], dg: &mut [f64]| dg.copy_from_slice(&[2.0 * x[0], 0.0, x[1], x[0]]);
+        let mut x = [1.0, 1.0];
+        let mut stats = SolverStats::default();
+
+        newton_method_system(residual, jacobian, 0.0, &mut x, 1e-10, &mut stats).unwrap();
+        assert!(abs!(x[0] - 2.0) < 1e-9 && abs!(x[1] - 3.0) < 1e-9);
+        assert_eq!(stats.jacobian_evaluations, stats.lu_decompositions);
+    }
+
+    #[test]
+    fn test_no_root() {
+        let func = SimpleDifferentiableFunction::new(|(_, x)| x * x + 1.0, |(_, x)| 2.0 * x);
+
+        assert!(matches!(
+            newton_method(func, 0.0, 2.0, 0.001, &mut SolverStats::default()),
+            Err(SolverError::NewtonNotConverged { .. })
+        ));
+    }
 }
@@ -1,39 +1,65 @@
-use crate::definitions::{InitialValueSystemProblem, PointwiseAdd, SampleableFunction, ScalarMul};
+use crate::definitions::{InitialValueSystemProblem, OdeSystem, Scalar, StateVector};
 use crate::generalized_explicit_k_step_method::{KStepMethod, KStepMethodStep};
 use crate::generalized_explicit_one_step_method::{OneStepMethod, OneStepMethodStep};
+use crate::method_info::MethodInfo;
+use crate::stats::SolverStats;
 
 /// 3rd order Nyström method.
 pub struct Nystroem3;
 
-impl<FT: SampleableFunction<(f64, Vec<f64>), f64>> KStepMethodStep<FT> for Nystroem3 {
-    fn step(&self, _k: usize, dfs: &[FT], t: f64, last_values: &[Vec<f64>], h: f64) -> Vec<f64> {
-        let last_values_owned: Vec<f64> = last_values[2].clone();
-        let before_last_values_owned: Vec<f64> = last_values[1].clone();
-        let before_before_last_values_owned: Vec<f64> = last_values[0].clone();
-        dfs.iter()
-            .map(|df| {
-                (7.0 / 3.0) * df.value_at((t, last_values_owned.clone()))
-                    - (2.0 / 3.0) * df.value_at((t - h, before_last_values_owned.clone()))
-                    + (1.0 / 3.0)
-                        * df.value_at((t - 2.0 * h, before_before_last_values_owned.clone()))
-            })
-            .collect::<Vec<f64>>()
-            .scalar_mul(h)
-            .pointwise_add(before_last_values_owned) // x_{n-1}
+impl<SYS: OdeSystem<S::Scalar>, S: StateVector> KStepMethodStep<SYS, S> for Nystroem3 {
+    fn step(
+        &self,
+        _system: &SYS,
+        _t: f64,
+        last_values: &[S],
+        last_slopes: &[S],
+        h: f64,
+        _stats: &mut SolverStats,
+    ) -> S {
+        let f_last = &last_slopes[2];
+        let f_before_last = &last_slopes[1];
+        let f_before_before_last = &last_slopes[0];
+
+        let h = S::Scalar::from_f64(h);
+
+        let mut next_values = last_values[1].clone(); // x_{n-1}
+        next_values.axpy(h * S::Scalar::from_ratio(7.0, 3.0), f_last);
+        next_values.axpy(h * S::Scalar::from_ratio(-2.0, 3.0), f_before_last);
+        next_values.axpy(h * S::Scalar::from_ratio(1.0, 3.0), f_before_before_last);
+        next_values
+    }
+}
+
+impl MethodInfo for Nystroem3 {
+    fn name(&self) -> &str {
+        "Nyström 3"
+    }
+
+    fn order(&self) -> usize {
+        3
+    }
+
+    fn stages(&self) -> usize {
+        1
+    }
+
+    fn steps(&self) -> usize {
+        3
     }
 }
 
 /// Makes a system of ODEs into a sampleable function using an 3rd order Nyström method.
 pub fn make_nystroem_3_method<
-    FT: SampleableFunction<(f64, Vec<f64>), f64>,
-    StartStep: OneStepMethodStep<FT>,
+    SYS: OdeSystem<S::Scalar>,
+    S: StateVector,
+    StartStep: OneStepMethodStep<SYS, S>,
+    IvpGen: Fn() -> InitialValueSystemProblem<SYS, S> + 'static,
+    StartGen: Fn(InitialValueSystemProblem<SYS, S>, f64) -> OneStepMethod<SYS, StartStep, S> + 'static,
 >(
-    ivp: fn() -> InitialValueSystemProblem<FT>,
+    ivp: IvpGen,
     h: f64,
-    start_method_gen: fn(
-        ivp: InitialValueSystemProblem<FT>,
-        h: f64,
-    ) -> OneStepMethod<FT, StartStep>,
-) -> KStepMethod<FT, Nystroem3, StartStep> {
-    KStepMethod::new(ivp, h, 3, Nystroem3, start_method_gen)
+    start_method_gen: StartGen,
+) -> KStepMethod<SYS, Nystroem3, StartStep, S> {
+    KStepMethod::new(Box::new(ivp), h, 3, Nystroem3, Box::new(start_method_gen))
 }
@@ -0,0 +1,111 @@
+use crate::definitions::{Scalar, SolverError};
+use std::ops::ControlFlow;
+
+/// Gets notified by the solvers while they step, e.g. to report progress or to cancel long runs.
+/// Returning `ControlFlow::Break` from any of the callbacks stops the solver with `SolverError::Cancelled`.
+/// Every callback does nothing by default.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, SolverError};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+/// use ngdl_rust::observer::Observer;
+/// use std::ops::ControlFlow;
+///
+/// // Gives up once the solution leaves [-2, 2]
+/// struct Bounded;
+///
+/// impl Observer for Bounded {
+///     fn accepted(&mut self, _t: f64, _h: f64, state: &[f64]) -> ControlFlow<()> {
+///         if state[0].abs() > 2.0 {
+///             ControlFlow::Break(())
+///         } else {
+///             ControlFlow::Continue(())
+///         }
+///     }
+/// }
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = y[0];
+/// let method = make_classic_runge_kutta(InitialValueSystemProblem::new(0.0, vec![1.0], rhs), 0.1);
+///
+/// match method.interval_observed(5.0, 0, &mut Bounded) {
+///     Err(SolverError::Cancelled { t }) => assert!((t - 0.7).abs() < 1e-12),
+///     _ => panic!("Should have been cancelled"),
+/// }
+/// ```
+pub trait Observer<N: Scalar = f64> {
+    /// A step of size h was accepted and ended in (t, state).
+    fn accepted(&mut self, _t: f64, _h: f64, _state: &[N]) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// An adaptive method rejected a step of size h starting at t, `error` is the estimate that was too large.
+    fn rejected(&mut self, _t: f64, _h: f64, _error: f64) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// (t, state) is passed on to the solution.
+    fn output(&mut self, _t: f64, _state: &[N]) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Prints the current t and h every n accepted steps to stderr.
+/// Silent by default, enable it with `every`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ProgressReporter {
+    // 0 for silent
+    every: usize,
+    accepted: usize,
+    rejected: usize,
+}
+
+impl ProgressReporter {
+    /// Reports every n accepted steps.
+    pub fn every(n: usize) -> Self {
+        ProgressReporter {
+            every: n,
+            ..Default::default()
+        }
+    }
+}
+
+impl<N: Scalar> Observer<N> for ProgressReporter {
+    fn accepted(&mut self, t: f64, h: f64, _state: &[N]) -> ControlFlow<()> {
+        self.accepted += 1;
+        if self.every > 0 && self.accepted.is_multiple_of(self.every) {
+            eprintln!(
+                "Current t: {}\nCurrent h: {:E}\nSteps: {} accepted, {} rejected\n",
+                t, h, self.accepted, self.rejected
+            );
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn rejected(&mut self, _t: f64, _h: f64, _error: f64) -> ControlFlow<()> {
+        self.rejected += 1;
+        ControlFlow::Continue(())
+    }
+}
+
+/// Passes the steps on to the wrapped observer, but not the output.
+/// For solvers that output interpolated values instead of the states they step through.
+pub(crate) struct StepsOnly<'a, N: Scalar>(pub &'a mut dyn Observer<N>);
+
+impl<N: Scalar> Observer<N> for StepsOnly<'_, N> {
+    fn accepted(&mut self, t: f64, h: f64, state: &[N]) -> ControlFlow<()> {
+        self.0.accepted(t, h, state)
+    }
+
+    fn rejected(&mut self, t: f64, h: f64, error: f64) -> ControlFlow<()> {
+        self.0.rejected(t, h, error)
+    }
+}
+
+/// Turns the answer of an observer at t into an error if it asked to stop.
+pub(crate) fn proceed(flow: ControlFlow<()>, t: f64) -> Result<(), SolverError> {
+    match flow {
+        ControlFlow::Continue(()) => Ok(()),
+        ControlFlow::Break(()) => Err(SolverError::Cancelled { t }),
+    }
+}
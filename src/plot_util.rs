@@ -4,8 +4,8 @@ use gnuplot::{Axes2D, PlotOption};
 /// Plots a number of points as line on the given axis.
 pub fn plot_line_on(axis: &mut Axes2D, points: &[Point2D], options: &[PlotOption<&str>]) {
     axis.lines(
-        &points.iter().map(|p| p.x).collect::<Vec<f64>>(),
-        &points.iter().map(|p| p.y).collect::<Vec<f64>>(),
+        points.iter().map(|p| p.x).collect::<Vec<f64>>(),
+        points.iter().map(|p| p.y).collect::<Vec<f64>>(),
         options,
     );
 }
@@ -13,8 +13,8 @@ pub fn plot_line_on(axis: &mut Axes2D, points: &[Point2D], options: &[PlotOption
 /// Plots a number of points as line with markers on the given axis.
 pub fn plot_line_points_on(axis: &mut Axes2D, points: &[Point2D], options: &[PlotOption<&str>]) {
     axis.lines_points(
-        &points.iter().map(|p| p.x).collect::<Vec<f64>>(),
-        &points.iter().map(|p| p.y).collect::<Vec<f64>>(),
+        points.iter().map(|p| p.x).collect::<Vec<f64>>(),
+        points.iter().map(|p| p.y).collect::<Vec<f64>>(),
         options,
     );
 }
@@ -22,8 +22,8 @@ pub fn plot_line_points_on(axis: &mut Axes2D, points: &[Point2D], options: &[Plo
 /// Plots a number of points the given axis.
 pub fn plot_points_on(axis: &mut Axes2D, points: &[Point2D], options: &[PlotOption<&str>]) {
     axis.points(
-        &points.iter().map(|p| p.x).collect::<Vec<f64>>(),
-        &points.iter().map(|p| p.y).collect::<Vec<f64>>(),
+        points.iter().map(|p| p.x).collect::<Vec<f64>>(),
+        points.iter().map(|p| p.y).collect::<Vec<f64>>(),
         options,
     );
 }
@@ -1,13 +1,13 @@
-use crate::definitions::{Interval, SampleableFunction};
+use crate::definitions::{Interval, SampleableFunction, Scalar};
+use crate::ln;
 use crate::util::make_supporting_points;
-use crate::{abs, ln};
 
 /// Results of a quadrature test run.
 /// Contains all data for further analysis.
 #[derive(Debug, Copy, Clone)]
-pub struct QuadratureTestResult {
+pub struct QuadratureTestResult<N: Scalar = f64> {
     /// Resulting integral value
-    pub value: f64,
+    pub value: N,
     /// |value - exact|
     pub abs_error: f64,
     /// How many sub intervals were used to obtain the result.
@@ -17,43 +17,49 @@ pub struct QuadratureTestResult {
 }
 
 /// Type alias for a quadrature method.
-pub trait QuadratureFormula<FT: SampleableFunction<f64, f64>> {
+/// The integrand is sampled on the real line, but its values can be of any scalar type, e.g. complex.
+pub trait QuadratureFormula<FT: SampleableFunction<f64, N>, N: Scalar = f64> {
     /// This does quadrature for one sub-interval.
     /// Basically just drop the formulas in here.
-    fn apply(&self, f: &FT, interval: &Interval) -> f64;
+    fn apply(&self, f: &FT, interval: &Interval) -> N;
 }
 
 /// Implementation of the trapezoid formula
 pub struct TrapezoidFormula;
 
-impl<FT: SampleableFunction<f64, f64>> QuadratureFormula<FT> for TrapezoidFormula {
-    fn apply(&self, f: &FT, interval: &Interval) -> f64 {
-        (interval.span() / 2.0) * (f.value_at(interval.start()) + f.value_at(interval.end()))
+impl<N: Scalar, FT: SampleableFunction<f64, N>> QuadratureFormula<FT, N> for TrapezoidFormula {
+    fn apply(&self, f: &FT, interval: &Interval) -> N {
+        N::from_ratio(interval.span(), 2.0)
+            * (f.value_at(interval.start()) + f.value_at(interval.end()))
     }
 }
 
 /// Implementation of the trapezoid formula
 pub struct KeplerFormula;
 
-impl<FT: SampleableFunction<f64, f64>> QuadratureFormula<FT> for KeplerFormula {
-    fn apply(&self, f: &FT, interval: &Interval) -> f64 {
+impl<N: Scalar, FT: SampleableFunction<f64, N>> QuadratureFormula<FT, N> for KeplerFormula {
+    fn apply(&self, f: &FT, interval: &Interval) -> N {
         let mid = interval.start() + (1.0 / 2.0) * (interval.end() - interval.start());
-        (interval.span() / 6.0)
-            * (f.value_at(interval.start()) + 4.0 * f.value_at(mid) + f.value_at(interval.end()))
+        N::from_ratio(interval.span(), 6.0)
+            * (f.value_at(interval.start())
+                + N::from_f64(4.0) * f.value_at(mid)
+                + f.value_at(interval.end()))
     }
 }
 
 /// Implementation of the trapezoid formula
 pub struct NewtonThreeEightFormula;
 
-impl<FT: SampleableFunction<f64, f64>> QuadratureFormula<FT> for NewtonThreeEightFormula {
-    fn apply(&self, f: &FT, interval: &Interval) -> f64 {
+impl<N: Scalar, FT: SampleableFunction<f64, N>> QuadratureFormula<FT, N>
+    for NewtonThreeEightFormula
+{
+    fn apply(&self, f: &FT, interval: &Interval) -> N {
         let mid1 = interval.start() + (1.0 / 3.0) * (interval.end() - interval.start());
         let mid2 = interval.start() + (2.0 / 3.0) * (interval.end() - interval.start());
-        (interval.span() / 8.0)
+        N::from_ratio(interval.span(), 8.0)
             * (f.value_at(interval.start())
-                + 3.0 * f.value_at(mid1)
-                + 3.0 * f.value_at(mid2)
+                + N::from_f64(3.0) * f.value_at(mid1)
+                + N::from_f64(3.0) * f.value_at(mid2)
                 + f.value_at(interval.end()))
     }
 }
@@ -80,30 +86,34 @@ impl<FT: SampleableFunction<f64, f64>> QuadratureFormula<FT> for NewtonThreeEigh
 ///
 /// dbg!(integrate(&f, interval, 1000));
 /// ```
-pub fn quadrature<FT: SampleableFunction<f64, f64>, QT: QuadratureFormula<FT>>(
+pub fn quadrature<N: Scalar, FT: SampleableFunction<f64, N>, QT: QuadratureFormula<FT, N>>(
     method: &QT,
     f: &FT,
     interval: Interval,
     n_splits: usize,
-) -> f64 {
+) -> N {
     let pts = make_supporting_points(n_splits, interval);
     quadrature_with_supporting_points(method, f, &pts)
 }
 
 /// Runs the supplied quadrature method for every number of splits from 1 to `up_to_splits`.
-pub fn quadrature_test_run<FT: SampleableFunction<f64, f64>, QT: QuadratureFormula<FT>>(
+pub fn quadrature_test_run<
+    N: Scalar,
+    FT: SampleableFunction<f64, N>,
+    QT: QuadratureFormula<FT, N>,
+>(
     method: &QT,
     f: FT,
-    exact: f64,
+    exact: N,
     interval: Interval,
     up_to_splits: usize,
-) -> Vec<QuadratureTestResult> {
+) -> Vec<QuadratureTestResult<N>> {
     (1..=up_to_splits)
         .map(|n| {
             let value = quadrature(method, &f, interval, n);
             QuadratureTestResult {
                 value,
-                abs_error: abs!(exact - value),
+                abs_error: (exact - value).modulus(),
                 splits_n: n,
                 h: interval.span() / n as f64,
             }
@@ -112,14 +122,15 @@ pub fn quadrature_test_run<FT: SampleableFunction<f64, f64>, QT: QuadratureFormu
 }
 
 fn quadrature_with_supporting_points<
-    FT: SampleableFunction<f64, f64>,
-    QT: QuadratureFormula<FT>,
+    N: Scalar,
+    FT: SampleableFunction<f64, N>,
+    QT: QuadratureFormula<FT, N>,
 >(
     method: &QT,
     f: &FT,
     points: &[f64],
-) -> f64 {
-    let mut sum = 0.0;
+) -> N {
+    let mut sum = N::zero();
     for i in 0..points.len() - 1 {
         sum += method.apply(f, &Interval::new(points[i], points[i + 1]));
     }
@@ -128,6 +139,9 @@ fn quadrature_with_supporting_points<
 
 /// Specialty function for task 1.
 /// Could be made more general.
-pub fn get_convergence_order(run1: &QuadratureTestResult, run2: &QuadratureTestResult) -> f64 {
+pub fn get_convergence_order<N: Scalar>(
+    run1: &QuadratureTestResult<N>,
+    run2: &QuadratureTestResult<N>,
+) -> f64 {
     (ln!(run2.abs_error) - ln!(run1.abs_error)) / (ln!(run2.h) - ln!(run1.h))
 }
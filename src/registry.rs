@@ -0,0 +1,119 @@
+use crate::embedded_rk::EmbeddedTableau;
+use crate::explicit_runge_kutta::Tableau;
+use crate::method_info::MethodInfo;
+use crate::solve::Method;
+
+/// Maps identifiers like "rk4" or "dopri5" to methods, e.g. to choose them from the command line.
+/// `default()` knows all methods of this crate, more can be added with `register`.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::InitialValueSystemProblem;
+/// use ngdl_rust::explicit_runge_kutta::Tableau;
+/// use ngdl_rust::method_info::MethodInfo;
+/// use ngdl_rust::registry::MethodRegistry;
+/// use ngdl_rust::solve::{solve, Method, SolverOptions};
+///
+/// let mut registry = MethodRegistry::default();
+/// let ralston = Tableau::new(vec![0.0, 2.0 / 3.0], vec![0.25, 0.75], vec![vec![], vec![2.0 / 3.0]]);
+/// registry.register("ralston", Method::RungeKutta(ralston.with_name("Ralston")));
+///
+/// let ids: Vec<&str> = registry.iter().map(|(id, _)| id).collect();
+/// assert_eq!(ids[..3], ["euler", "modified-euler", "implicit-euler"]);
+/// assert_eq!(ids.last(), Some(&"ralston"));
+///
+/// let dopri5 = registry.get("dopri5").unwrap();
+/// assert_eq!(dopri5.order(), 5);
+/// assert!(dopri5.is_adaptive());
+///
+/// let method = registry.get("ralston").unwrap();
+/// assert_eq!(method.name(), "Ralston");
+/// assert_eq!((method.order(), method.stages(), method.steps()), (2, 2, 1));
+/// assert!(!method.is_adaptive());
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+/// let problem = InitialValueSystemProblem::new(0.0, vec![1.0], rhs);
+/// assert!(solve(problem, method, SolverOptions::until(1.0)).is_ok());
+/// ```
+pub struct MethodRegistry {
+    // Kept in the order of registration for listings
+    methods: Vec<(String, Method)>,
+}
+
+impl MethodRegistry {
+    /// A registry without any methods.
+    pub fn empty() -> Self {
+        MethodRegistry {
+            methods: Vec::new(),
+        }
+    }
+
+    /// Adds the method under id, replacing a method registered under the same id before.
+    pub fn register(&mut self, id: &str, method: Method) {
+        match self.methods.iter_mut().find(|(known, _)| known == id) {
+            Some((_, known_method)) => *known_method = method,
+            None => self.methods.push((id.to_string(), method)),
+        }
+    }
+
+    /// The method registered under id.
+    pub fn get(&self, id: &str) -> Option<&Method> {
+        self.methods
+            .iter()
+            .find(|(known, _)| known == id)
+            .map(|(_, method)| method)
+    }
+
+    /// All identifiers with their methods, the metadata is available via `MethodInfo`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Method)> {
+        self.methods
+            .iter()
+            .map(|(id, method)| (id.as_str(), method))
+    }
+
+    /// Identifiers and names of all methods, one per line.
+    pub fn describe(&self) -> String {
+        self.iter()
+            .map(|(id, method)| {
+                format!(
+                    "{}: {}, order {}, {} stages, {} steps{}",
+                    id,
+                    method.name(),
+                    method.order(),
+                    method.stages(),
+                    method.steps(),
+                    if method.is_adaptive() {
+                        ", adaptive"
+                    } else {
+                        ""
+                    }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Default for MethodRegistry {
+    fn default() -> Self {
+        let mut registry = MethodRegistry::empty();
+        registry.register("euler", Method::ExplicitEuler);
+        registry.register("modified-euler", Method::ModifiedEuler);
+        registry.register("implicit-euler", Method::ImplicitEuler);
+        registry.register("rk4", Method::rk4());
+        registry.register("england", Method::RungeKutta(Tableau::england()));
+        registry.register("3/8", Method::RungeKutta(Tableau::three_eight()));
+        registry.register("midpoint", Method::RungeKutta(Tableau::second_order()));
+        registry.register("heun", Method::RungeKutta(Tableau::heun()));
+        registry.register(
+            "heun-euler",
+            Method::Embedded(EmbeddedTableau::heun_euler()),
+        );
+        registry.register("dopri5", Method::dopri5());
+        registry.register("ab2", Method::AdamsBashforth2);
+        registry.register("ab3", Method::AdamsBashforth3);
+        registry.register("nystroem3", Method::Nystroem3);
+        registry.register("milne-simpson", Method::MilneSimpson);
+        registry
+    }
+}
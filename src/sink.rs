@@ -0,0 +1,410 @@
+use crate::definitions::Scalar;
+use crate::solution::Solution;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Receives the states of a solver while it steps, e.g. to stream them to disk with bounded memory.
+/// `Solution` is the sink that keeps everything in memory.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::InitialValueSystemProblem;
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+/// use ngdl_rust::sink::CsvSink;
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+///     dydt[0] = y[1];
+///     dydt[1] = -y[0];
+/// };
+/// let method = make_classic_runge_kutta(InitialValueSystemProblem::new(0.0, vec![1.0, 0.0], rhs), 0.5);
+///
+/// let mut sink = CsvSink::new(Vec::new(), &["x", "v"]);
+/// method.interval_into(1.0, 0, &mut sink).unwrap();
+///
+/// let csv = String::from_utf8(sink.into_inner()).unwrap();
+/// assert_eq!(csv.lines().next(), Some("t,x,v"));
+/// assert_eq!(csv.lines().count(), 4);
+/// ```
+pub trait SolutionSink<N: Scalar = f64> {
+    /// Receives the state at t.
+    fn write_state(&mut self, t: f64, state: &[N]) -> io::Result<()>;
+
+    /// Called once after the last state, e.g. to flush buffers.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<N: Scalar> SolutionSink<N> for Solution<N> {
+    fn write_state(&mut self, t: f64, state: &[N]) -> io::Result<()> {
+        self.push(t, state);
+        Ok(())
+    }
+}
+
+/// Writes one line "t,y_0,y_1,..." per state after a header line with the names of the components.
+pub struct CsvSink<W: Write> {
+    writer: W,
+    names: Vec<String>,
+    header_written: bool,
+}
+
+impl<W: Write> CsvSink<W> {
+    /// Names the columns after t with `names`, "y0", "y1", ... are used if it is empty.
+    pub fn new(writer: W, names: &[&str]) -> Self {
+        CsvSink {
+            writer,
+            names: names.iter().map(|name| name.to_string()).collect(),
+            header_written: false,
+        }
+    }
+
+    /// The underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl CsvSink<BufWriter<File>> {
+    /// Writes to a new file at path.
+    pub fn create<P: AsRef<Path>>(path: P, names: &[&str]) -> io::Result<Self> {
+        Ok(CsvSink::new(BufWriter::new(File::create(path)?), names))
+    }
+}
+
+impl<W: Write> SolutionSink for CsvSink<W> {
+    fn write_state(&mut self, t: f64, state: &[f64]) -> io::Result<()> {
+        if !self.header_written {
+            if self.names.is_empty() {
+                self.names = (0..state.len()).map(|idx| format!("y{}", idx)).collect();
+            }
+            writeln!(self.writer, "t,{}", self.names.join(","))?;
+            self.header_written = true;
+        }
+
+        write!(self.writer, "{:?}", t)?;
+        for val in state {
+            write!(self.writer, ",{:?}", val)?;
+        }
+        writeln!(self.writer)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes one JSON object {"t": t, "y": [y_0, y_1, ...]} per line.
+pub struct JsonLinesSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    /// Writes to writer.
+    pub fn new(writer: W) -> Self {
+        JsonLinesSink { writer }
+    }
+
+    /// The underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl JsonLinesSink<BufWriter<File>> {
+    /// Writes to a new file at path.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(JsonLinesSink::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> SolutionSink for JsonLinesSink<W> {
+    fn write_state(&mut self, t: f64, state: &[f64]) -> io::Result<()> {
+        // The solvers only pass on finite states, so Debug always gives valid JSON numbers
+        let ys: Vec<String> = state.iter().map(|val| format!("{:?}", val)).collect();
+        writeln!(self.writer, "{{\"t\":{:?},\"y\":[{}]}}", t, ys.join(","))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes the rows t, y_0, y_1, ... as little-endian f64 without any header.
+pub struct BinarySink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> BinarySink<W> {
+    /// Writes to writer.
+    pub fn new(writer: W) -> Self {
+        BinarySink { writer }
+    }
+
+    /// The underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl BinarySink<BufWriter<File>> {
+    /// Writes to a new file at path.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(BinarySink::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> SolutionSink for BinarySink<W> {
+    fn write_state(&mut self, t: f64, state: &[f64]) -> io::Result<()> {
+        write_row(&mut self.writer, t, state)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes a NumPy .npy file holding a (rows, 1 + dim) array of little-endian f64,
+/// every row being t, y_0, y_1, ...
+/// The number of rows is only known at the end, so `finish` has to be called to patch the header.
+pub struct NpySink<W: Write + Seek> {
+    writer: W,
+    rows: usize,
+    columns: usize,
+}
+
+/// Enough digits for every row count, so the patched header has the same length
+const NPY_ROWS_WIDTH: usize = 20;
+
+impl<W: Write + Seek> NpySink<W> {
+    /// Writes to writer.
+    pub fn new(writer: W) -> Self {
+        NpySink {
+            writer,
+            rows: 0,
+            columns: 0,
+        }
+    }
+
+    /// The underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let mut header = format!(
+            "{{'descr': '<f8', 'fortran_order': False, 'shape': ({:>width$}, {}), }}",
+            self.rows,
+            self.columns,
+            width = NPY_ROWS_WIDTH
+        );
+        // Magic, version and header length take 10 bytes, the data has to start 64 byte aligned
+        let padding = 64 - (10 + header.len() + 1) % 64;
+        header.push_str(&" ".repeat(padding % 64));
+        header.push('\n');
+
+        self.writer.write_all(b"\x93NUMPY\x01\x00")?;
+        self.writer
+            .write_all(&(header.len() as u16).to_le_bytes())?;
+        self.writer.write_all(header.as_bytes())
+    }
+}
+
+impl NpySink<BufWriter<File>> {
+    /// Writes to a new file at path.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(NpySink::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write + Seek> SolutionSink for NpySink<W> {
+    fn write_state(&mut self, t: f64, state: &[f64]) -> io::Result<()> {
+        if self.rows == 0 {
+            self.columns = 1 + state.len();
+            // Placeholder, patched in finish
+            self.write_header()?;
+        }
+        self.rows += 1;
+        write_row(&mut self.writer, t, state)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        if self.rows > 0 {
+            self.writer.seek(SeekFrom::Start(end))?;
+        }
+        self.writer.flush()
+    }
+}
+
+fn write_row<W: Write>(writer: &mut W, t: f64, state: &[f64]) -> io::Result<()> {
+    writer.write_all(&t.to_le_bytes())?;
+    for val in state {
+        writer.write_all(&val.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::{InitialValueSystemProblem, SolverError};
+    use crate::explicit_runge_kutta::make_classic_runge_kutta;
+    use std::convert::TryInto;
+    use std::io::{Cursor, ErrorKind};
+
+    /// Accepts or rejects every write, flushing always fails.
+    struct FailingWriter {
+        writes_fail: bool,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.writes_fail {
+                Err(io::Error::new(ErrorKind::BrokenPipe, "write"))
+            } else {
+                Ok(buf.len())
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::new(ErrorKind::BrokenPipe, "flush"))
+        }
+    }
+
+    /// Buffers everything, so nothing reaches the vector before `finish`.
+    fn buffered() -> BufWriter<Vec<u8>> {
+        BufWriter::with_capacity(1 << 16, Vec::new())
+    }
+
+    fn write_rows<K: SolutionSink>(sink: &mut K) {
+        sink.write_state(0.0, &[1.0, -2.5]).unwrap();
+        sink.write_state(0.5, &[0.25, 3.0]).unwrap();
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let mut sink = CsvSink::new(buffered(), &[]);
+        write_rows(&mut sink);
+        assert!(sink.writer.get_ref().is_empty());
+        sink.finish().unwrap();
+
+        let csv = String::from_utf8(sink.into_inner().into_inner().unwrap()).unwrap();
+        assert_eq!(csv, "t,y0,y1\n0.0,1.0,-2.5\n0.5,0.25,3.0\n");
+
+        let mut sink = CsvSink::new(Vec::new(), &["x", "v"]);
+        write_rows(&mut sink);
+        let csv = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(csv.lines().next(), Some("t,x,v"));
+    }
+
+    #[test]
+    fn test_json_lines_rows() {
+        let mut sink = JsonLinesSink::new(buffered());
+        write_rows(&mut sink);
+        assert!(sink.writer.get_ref().is_empty());
+        sink.finish().unwrap();
+
+        let json = String::from_utf8(sink.into_inner().into_inner().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            "{\"t\":0.0,\"y\":[1.0,-2.5]}\n{\"t\":0.5,\"y\":[0.25,3.0]}\n"
+        );
+    }
+
+    #[test]
+    fn test_binary_rows() {
+        let mut sink = BinarySink::new(buffered());
+        write_rows(&mut sink);
+        assert!(sink.writer.get_ref().is_empty());
+        sink.finish().unwrap();
+
+        let data: Vec<f64> = sink
+            .into_inner()
+            .into_inner()
+            .unwrap()
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(data, vec![0.0, 1.0, -2.5, 0.5, 0.25, 3.0]);
+    }
+
+    #[test]
+    fn test_write_errors_reach_the_solver() {
+        let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+        let method =
+            make_classic_runge_kutta(InitialValueSystemProblem::new(0.0, vec![1.0], rhs), 0.1);
+        let expected = Err(SolverError::Output {
+            kind: ErrorKind::BrokenPipe,
+        });
+
+        // Failing while stepping and failing in finish
+        for writes_fail in [true, false] {
+            let writer = || FailingWriter { writes_fail };
+            assert_eq!(
+                method.interval_into(1.0, 0, &mut CsvSink::new(writer(), &[])),
+                expected
+            );
+            assert_eq!(
+                method.interval_into(1.0, 0, &mut JsonLinesSink::new(writer())),
+                expected
+            );
+            assert_eq!(
+                method.interval_into(1.0, 0, &mut BinarySink::new(writer())),
+                expected
+            );
+        }
+    }
+
+    /// The shape in the header and the data of an npy file.
+    fn read_npy(bytes: &[u8]) -> ((usize, usize), Vec<f64>) {
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let data_start = 10 + header_len;
+        assert_eq!(data_start % 64, 0);
+
+        let header = std::str::from_utf8(&bytes[10..data_start]).unwrap();
+        assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, "));
+        assert!(header.ends_with('\n'));
+        let shape = header.split("'shape': (").nth(1).unwrap();
+        let shape = &shape[..shape.find(')').unwrap()];
+        let dims: Vec<usize> = shape
+            .split(',')
+            .map(|d| d.trim().parse().unwrap())
+            .collect();
+
+        let data = bytes[data_start..]
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        ((dims[0], dims[1]), data)
+    }
+
+    #[test]
+    fn test_npy_header_patched() {
+        let mut sink = NpySink::new(Cursor::new(Vec::new()));
+        for row in 0..3 {
+            let t = row as f64;
+            sink.write_state(t, &[2.0 * t, -t]).unwrap();
+        }
+        sink.finish().unwrap();
+
+        let (shape, data) = read_npy(sink.into_inner().get_ref());
+        assert_eq!(shape, (3, 3));
+        assert_eq!(data, vec![0.0, 0.0, -0.0, 1.0, 2.0, -1.0, 2.0, 4.0, -2.0]);
+    }
+
+    #[test]
+    fn test_npy_empty() {
+        let mut sink = NpySink::new(Cursor::new(Vec::new()));
+        sink.finish().unwrap();
+
+        let (shape, data) = read_npy(sink.into_inner().get_ref());
+        assert_eq!(shape, (0, 0));
+        assert!(data.is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::abs;
+use crate::definitions::{Point2D, Scalar, SolverError};
+use crate::stats::SolverStats;
+use crate::util::{before_target, directed_step};
+use std::iter::{Copied, Zip};
+use std::slice::{ChunksExact, Iter};
+
+/// The result of solving an initial value problem.
+/// Holds the times once and the states as a matrix with one row per time,
+/// so every component can be accessed as a series without copying `t` around.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, ODEMethod};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+///     dydt[0] = y[1];
+///     dydt[1] = -y[0];
+/// };
+/// let problem = InitialValueSystemProblem::new(0.0, vec![1.0, 0.0], rhs);
+///
+/// let solution = make_classic_runge_kutta(problem, 0.1).interval(3.0, 0).unwrap();
+///
+/// let positions = solution.component(0);
+/// let orbit = solution.phase_plane(0, 1);
+/// for (t, y) in &solution {
+///     assert_eq!(y.len(), 2);
+/// }
+/// assert_eq!(solution.final_time(), Some(3.0));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution<N: Scalar = f64> {
+    dim: usize,
+    ts: Vec<f64>,
+    // Row major, row i belongs to ts[i]
+    states: Vec<N>,
+    stats: SolverStats,
+}
+
+impl<N: Scalar> Solution<N> {
+    /// Empty solution for states with `dim` components.
+    pub fn new(dim: usize) -> Self {
+        Solution::with_capacity(dim, 0)
+    }
+
+    /// Empty solution with room for `capacity` states.
+    ///
+    /// # Panics
+    /// If `dim` is zero.
+    pub fn with_capacity(dim: usize, capacity: usize) -> Self {
+        assert!(dim > 0, "States need at least one component");
+        Solution {
+            dim,
+            ts: Vec::with_capacity(capacity),
+            states: Vec::with_capacity(capacity * dim),
+            stats: SolverStats::default(),
+        }
+    }
+
+    /// Appends the state at t.
+    ///
+    /// # Panics
+    /// If the state does not have `dim` components.
+    pub fn push(&mut self, t: f64, state: &[N]) {
+        assert_eq!(
+            state.len(),
+            self.dim,
+            "State has the wrong number of components"
+        );
+        self.ts.push(t);
+        self.states.extend_from_slice(state);
+    }
+
+    /// The work the solver did to get this solution.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
+    pub(crate) fn set_stats(&mut self, stats: SolverStats) {
+        self.stats = stats;
+    }
+
+    /// Number of stored states
+    pub fn len(&self) -> usize {
+        self.ts.len()
+    }
+
+    /// True if no state is stored
+    pub fn is_empty(&self) -> bool {
+        self.ts.is_empty()
+    }
+
+    /// Number of components of every state
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// All times
+    pub fn times(&self) -> &[f64] {
+        &self.ts
+    }
+
+    /// The state belonging to `times()[idx]`.
+    pub fn state(&self, idx: usize) -> &[N] {
+        &self.states[idx * self.dim..(idx + 1) * self.dim]
+    }
+
+    /// The series of values of one component over time.
+    pub fn component(&self, component: usize) -> Vec<N> {
+        self.iter().map(|(_, y)| y[component]).collect()
+    }
+
+    /// Time of the last state.
+    pub fn final_time(&self) -> Option<f64> {
+        self.ts.last().copied()
+    }
+
+    /// The last state.
+    pub fn final_state(&self) -> Option<&[N]> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.state(self.len() - 1))
+        }
+    }
+
+    /// Iterates over (t, state) pairs.
+    pub fn iter(&self) -> SolutionIter<'_, N> {
+        self.ts
+            .iter()
+            .copied()
+            .zip(self.states.chunks_exact(self.dim))
+    }
+}
+
+impl Solution<f64> {
+    /// One component over time as points (t, y_component), e.g. for `plot_util`.
+    pub fn component_points(&self, component: usize) -> Vec<Point2D> {
+        self.iter()
+            .map(|(t, y)| Point2D::new(t, y[component]))
+            .collect()
+    }
+
+    /// Two components plotted against each other, i.e. the points (y_x, y_y).
+    pub fn phase_plane(&self, x_component: usize, y_component: usize) -> Vec<Point2D> {
+        self.iter()
+            .map(|(_, y)| Point2D::new(y[x_component], y[y_component]))
+            .collect()
+    }
+
+    /// The old layout: a vector over t of the points (t, y_i) of all components.
+    pub fn to_points(&self) -> Vec<Vec<Point2D>> {
+        self.iter()
+            .map(|(t, y)| y.iter().map(|val| Point2D::new(t, *val)).collect())
+            .collect()
+    }
+}
+
+/// The times at which a solver reports the solution, instead of after every n-th step.
+/// The solvers hit these times exactly, either by shortening steps or by dense output.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, ODEMethod};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+/// use ngdl_rust::solution::OutputTimes;
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+/// let method = make_classic_runge_kutta(InitialValueSystemProblem::new(0.0, vec![1.0], rhs), 0.1);
+///
+/// let output = OutputTimes::Every { dt: 0.25, t_target: 1.1 };
+/// assert_eq!(output.times(0.0).unwrap(), vec![0.0, 0.25, 0.5, 0.75, 1.0, 1.1]);
+///
+/// let solution = method.interval_at(&output).unwrap();
+/// assert_eq!(solution.times(), &[0.0, 0.25, 0.5, 0.75, 1.0, 1.1]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputTimes {
+    /// Exactly these times, all on the same side of the start time.
+    At(Vec<f64>),
+    /// The start time and every multiple of dt after it up to t_target, and t_target itself.
+    Every {
+        /// Distance between two outputs
+        dt: f64,
+        /// Last output time
+        t_target: f64,
+    },
+}
+
+impl OutputTimes {
+    /// The output times for a problem starting at start_time, in the order of the integration.
+    ///
+    /// Fails with `SolverError::InvalidOutputTime` for times that are not finite,
+    /// times on both sides of start_time and a zero or non finite dt.
+    pub fn times(&self, start_time: f64) -> Result<Vec<f64>, SolverError> {
+        match self {
+            OutputTimes::At(ts) => {
+                if let Some(t) = ts.iter().find(|t| !t.is_finite()) {
+                    return Err(SolverError::InvalidOutputTime { t: *t });
+                }
+                let mut ts = ts.clone();
+                ts.sort_by(f64::total_cmp);
+                match (ts.first(), ts.last()) {
+                    (Some(t_first), Some(t_last)) if *t_first < start_time => {
+                        if *t_last > start_time {
+                            return Err(SolverError::InvalidOutputTime { t: *t_last });
+                        }
+                        ts.reverse();
+                    }
+                    _ => (),
+                }
+                Ok(ts)
+            }
+            OutputTimes::Every { dt, t_target } => {
+                if *dt == 0.0 || !dt.is_finite() || !t_target.is_finite() {
+                    return Err(SolverError::InvalidOutputTime { t: *t_target });
+                }
+                let dt = directed_step(*dt, start_time, *t_target);
+                // Multiply instead of adding up to not accumulate rounding errors
+                let mut ts: Vec<f64> = (0..)
+                    .map(|idx| start_time + idx as f64 * dt)
+                    .take_while(|t| !before_target(*t_target, *t, dt))
+                    .collect();
+                // Snap a last multiple that only misses t_target due to rounding
+                match ts.last_mut() {
+                    Some(t)
+                        if abs!(*t_target - *t) <= 4.0 * f64::EPSILON * abs!(t_target).max(1.0) =>
+                    {
+                        *t = *t_target
+                    }
+                    _ => ts.push(*t_target),
+                }
+                Ok(ts)
+            }
+        }
+    }
+}
+
+/// Iterator over the (t, state) pairs of a solution.
+pub type SolutionIter<'a, N> = Zip<Copied<Iter<'a, f64>>, ChunksExact<'a, N>>;
+
+impl<'a, N: Scalar> IntoIterator for &'a Solution<N> {
+    type Item = (f64, &'a [N]);
+    type IntoIter = SolutionIter<'a, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_output_times() {
+        let invalid = |t| Err(SolverError::InvalidOutputTime { t });
+
+        assert!(matches!(
+            OutputTimes::At(vec![0.5, f64::NAN]).times(0.0),
+            Err(SolverError::InvalidOutputTime { t }) if t.is_nan()
+        ));
+        assert_eq!(OutputTimes::At(vec![-1.0, 1.0]).times(0.0), invalid(1.0));
+        assert_eq!(OutputTimes::At(vec![1.0, 2.0]).times(1.5), invalid(2.0));
+        assert_eq!(
+            OutputTimes::Every {
+                dt: 0.0,
+                t_target: 1.0
+            }
+            .times(0.0),
+            invalid(1.0)
+        );
+
+        // Backwards, in the order of the integration
+        assert_eq!(
+            OutputTimes::At(vec![-1.0, 0.0, -0.5]).times(0.0),
+            Ok(vec![0.0, -0.5, -1.0])
+        );
+    }
+}
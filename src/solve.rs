@@ -0,0 +1,394 @@
+use crate::abs;
+use crate::adams_bashforth::{
+    make_adams_bashforth_2_method, make_adams_bashforth_3_method, AdamsBashford2, AdamsBashford3,
+};
+use crate::definitions::{InitialValueSystemProblem, OdeSystem, SolverError};
+use crate::embedded_rk::{make_embedded_runge_kutta, EmbeddedTableau, MAX_STEPS};
+use crate::euler_explicit::{make_explicit_euler_method_system, ExplicitEulerStep};
+use crate::events::{Event, SolutionWithEvents};
+use crate::explicit_runge_kutta::{
+    make_classic_runge_kutta, make_explicit_runge_kutta_with_tableau, Tableau,
+};
+use crate::implicit_euler::{make_implicit_euler_method_system, ImplicitEulerStep};
+use crate::method_info::MethodInfo;
+use crate::milne_simpson::{make_milne_simpson_method, MilneSimpson};
+use crate::modified_explicit_euler::{
+    make_modified_explicit_euler_method_system, ModifiedExplicitEulerStep,
+};
+use crate::nystroem::{make_nystroem_3_method, Nystroem3};
+use crate::observer::{Observer, ProgressReporter};
+use crate::solution::OutputTimes;
+use crate::step_size_controller::StepSizeController;
+use crate::tolerance::{ErrorControl, ErrorNorm, Tolerance};
+use num::complex::Complex64;
+
+/// The methods `solve` can run.
+/// Fixed step methods use h from the options as step size, adaptive ones as first try.
+/// The multistep methods compute their start values with the classic Runge-Kutta method.
+#[derive(Clone, Debug)]
+pub enum Method {
+    /// Explicit Euler method
+    ExplicitEuler,
+    /// Explicit Euler method using y'' as well
+    ModifiedEuler,
+    /// Implicit Euler method, solves for every step with Newton's method
+    ImplicitEuler,
+    /// Explicit Runge-Kutta method with the given tableau
+    RungeKutta(Tableau),
+    /// Adaptive embedded Runge-Kutta method with the given pair of tableaus
+    Embedded(EmbeddedTableau),
+    /// Adams-Bashforth method with 2 steps
+    AdamsBashforth2,
+    /// Adams-Bashforth method with 3 steps
+    AdamsBashforth3,
+    /// Nyström method of order 3
+    Nystroem3,
+    /// Milne-Simpson predictor-corrector method
+    MilneSimpson,
+}
+
+impl Method {
+    /// Classic 4-th order Runge-Kutta method.
+    pub fn rk4() -> Self {
+        Method::RungeKutta(Tableau::classic())
+    }
+
+    /// Dormand-Prince 5(4) with adaptive step sizes.
+    pub fn dopri5() -> Self {
+        Method::Embedded(EmbeddedTableau::dopri5())
+    }
+
+    /// Whether the method chooses its step sizes itself.
+    pub fn is_adaptive(&self) -> bool {
+        matches!(self, Method::Embedded(_))
+    }
+
+    fn info(&self) -> &dyn MethodInfo {
+        match self {
+            Method::ExplicitEuler => &ExplicitEulerStep,
+            Method::ModifiedEuler => &ModifiedExplicitEulerStep,
+            Method::ImplicitEuler => &ImplicitEulerStep,
+            Method::RungeKutta(tableau) => tableau,
+            Method::Embedded(tableau) => tableau,
+            Method::AdamsBashforth2 => &AdamsBashford2,
+            Method::AdamsBashforth3 => &AdamsBashford3,
+            Method::Nystroem3 => &Nystroem3,
+            Method::MilneSimpson => &MilneSimpson,
+        }
+    }
+}
+
+impl MethodInfo for Method {
+    fn name(&self) -> &str {
+        self.info().name()
+    }
+
+    fn order(&self) -> usize {
+        self.info().order()
+    }
+
+    fn stages(&self) -> usize {
+        self.info().stages()
+    }
+
+    fn steps(&self) -> usize {
+        self.info().steps()
+    }
+
+    fn is_explicit(&self) -> bool {
+        self.info().is_explicit()
+    }
+
+    fn stability_function(&self, z: Complex64) -> Option<Complex64> {
+        self.info().stability_function(z)
+    }
+}
+
+/// Which states end up in the solution.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputMode {
+    /// Every skip_n-th step up to t_target, like `ODEMethod::interval`
+    Steps {
+        /// Where the integration ends, may lie before the start time
+        t_target: f64,
+        /// Only every skip_n-th step is kept
+        skip_n: isize,
+    },
+    /// Exactly the given times, like `ODEMethod::interval_at`
+    Times(OutputTimes),
+}
+
+impl OutputMode {
+    /// The last time the integration has to reach.
+    fn t_end(&self, start_time: f64) -> Result<f64, SolverError> {
+        match self {
+            OutputMode::Steps { t_target, .. } => Ok(*t_target),
+            OutputMode::Times(output) => Ok(output
+                .times(start_time)?
+                .last()
+                .copied()
+                .unwrap_or(start_time)),
+        }
+    }
+}
+
+/// Everything about a run of `solve` except the problem and the method, built step by step.
+/// Settings a method has no use for are ignored, e.g. the tolerances by fixed step methods.
+pub struct SolverOptions {
+    output: OutputMode,
+    h: Option<f64>,
+    rtol: Tolerance,
+    atol: Tolerance,
+    norm: ErrorNorm,
+    controller: Option<Box<dyn StepSizeController>>,
+    max_steps: usize,
+    h_min: f64,
+    h_max: f64,
+    events: Vec<Event>,
+    observer: Option<Box<dyn Observer>>,
+}
+
+impl SolverOptions {
+    /// Integrates up to t_target and keeps every step.
+    pub fn until(t_target: f64) -> Self {
+        SolverOptions::with_output(OutputMode::Steps {
+            t_target,
+            skip_n: 0,
+        })
+    }
+
+    /// Keeps exactly the states at the output times.
+    pub fn at(output: OutputTimes) -> Self {
+        SolverOptions::with_output(OutputMode::Times(output))
+    }
+
+    /// Defaults for everything but the output.
+    pub fn with_output(output: OutputMode) -> Self {
+        SolverOptions {
+            output,
+            h: None,
+            rtol: Tolerance::Scalar(1e-6),
+            atol: Tolerance::Scalar(1e-6),
+            norm: ErrorNorm::Rms,
+            controller: None,
+            max_steps: MAX_STEPS,
+            h_min: 0.0,
+            h_max: f64::INFINITY,
+            events: Vec::new(),
+            observer: None,
+        }
+    }
+
+    /// Step size of fixed step methods and first step size of adaptive ones.
+    /// Fixed step methods default to a hundredth of the integration interval,
+    /// adaptive ones estimate their first step with `step_size_controller::initial_step_size`.
+    pub fn h(mut self, h: f64) -> Self {
+        self.h = Some(h);
+        self
+    }
+
+    /// Relative tolerance of adaptive methods, a number or one per component, 1e-6 by default.
+    pub fn rtol<R: Into<Tolerance>>(mut self, rtol: R) -> Self {
+        self.rtol = rtol.into();
+        self
+    }
+
+    /// Absolute tolerance of adaptive methods, a number or one per component, 1e-6 by default.
+    pub fn atol<A: Into<Tolerance>>(mut self, atol: A) -> Self {
+        self.atol = atol.into();
+        self
+    }
+
+    /// Norm of the error estimates of adaptive methods, the Hairer RMS norm by default.
+    pub fn norm(mut self, norm: ErrorNorm) -> Self {
+        self.norm = norm;
+        self
+    }
+
+    /// Chooses the step sizes of adaptive methods, the elementary `IController` by default.
+    pub fn controller<C: StepSizeController + 'static>(mut self, controller: C) -> Self {
+        self.controller = Some(Box::new(controller));
+        self
+    }
+
+    /// The run fails with `SolverError::MaxStepsExceeded` if more steps are needed, 1 000 000 by default.
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Adaptive methods fail with `SolverError::StepSizeUnderflow` below this step size.
+    pub fn h_min(mut self, h_min: f64) -> Self {
+        self.h_min = h_min;
+        self
+    }
+
+    /// Adaptive methods never take larger steps than this.
+    pub fn h_max(mut self, h_max: f64) -> Self {
+        self.h_max = h_max;
+        self
+    }
+
+    /// Only keeps every skip_n-th step, has no effect if the output are fixed times.
+    pub fn skip(mut self, skip_n: isize) -> Self {
+        if let OutputMode::Steps { skip_n: skip, .. } = &mut self.output {
+            *skip = skip_n;
+        }
+        self
+    }
+
+    /// Watches for the zero crossings of the event, can be called several times.
+    pub fn event(mut self, event: Event) -> Self {
+        self.events.push(event);
+        self
+    }
+
+    /// Reports the steps of the run to the observer, which may also cancel it.
+    pub fn observer<O: Observer + 'static>(mut self, observer: O) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+}
+
+/// Solves the problem with the method, everything else is configured by the options.
+/// Returns the solution together with the located events.
+/// Fails with `SolverError::EventsWithOutputTimes` if events are combined with fixed output times.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::InitialValueSystemProblem;
+/// use ngdl_rust::solve::{solve, Method, SolverOptions};
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+/// let problem = InitialValueSystemProblem::new(0.0, vec![1.0], rhs);
+///
+/// for method in [Method::rk4(), Method::dopri5(), Method::AdamsBashforth3] {
+///     let options = SolverOptions::until(1.0).h(0.01).rtol(1e-8).atol(1e-8);
+///     let (solution, _) = solve(problem.clone(), &method, options).unwrap();
+///
+///     let (t, y) = (solution.final_time().unwrap(), solution.final_state().unwrap()[0]);
+///     assert!((y - (-t).exp()).abs() < 1e-5);
+/// }
+/// ```
+pub fn solve<SYS: OdeSystem + Clone + 'static>(
+    problem: InitialValueSystemProblem<SYS>,
+    method: &Method,
+    options: SolverOptions,
+) -> Result<SolutionWithEvents, SolverError> {
+    let SolverOptions {
+        output,
+        h,
+        rtol,
+        atol,
+        norm,
+        controller,
+        max_steps,
+        h_min,
+        h_max,
+        events,
+        mut observer,
+    } = options;
+    if !events.is_empty() && matches!(output, OutputMode::Times(_)) {
+        return Err(SolverError::EventsWithOutputTimes);
+    }
+
+    let start_time = problem.start_time;
+    let t_end = output.t_end(start_time)?;
+    let automatic_h = h.is_none();
+    let h = h.unwrap_or(abs!(t_end - start_time) / 100.0);
+    let mut default_observer = ProgressReporter::default();
+    let observer: &mut dyn Observer = match observer.as_deref_mut() {
+        Some(observer) => observer,
+        None => &mut default_observer,
+    };
+
+    if !method.is_adaptive() {
+        // The number of steps is known in advance
+        let steps = (abs!(t_end - start_time) / h).ceil();
+        if steps > max_steps as f64 {
+            return Err(SolverError::MaxStepsExceeded {
+                t: start_time,
+                max_steps,
+            });
+        }
+    }
+
+    macro_rules! run {
+        ($method: expr) => {
+            match &output {
+                OutputMode::Steps { t_target, skip_n } => {
+                    $method.run(*t_target, *skip_n, &events, observer)
+                }
+                OutputMode::Times(times) => Ok(($method.run_at(times, observer)?, Vec::new())),
+            }
+        };
+    }
+
+    let create_problem = move || problem.clone();
+    match method {
+        Method::ExplicitEuler => run!(make_explicit_euler_method_system(create_problem(), h)),
+        Method::ModifiedEuler => run!(make_modified_explicit_euler_method_system(
+            create_problem(),
+            h
+        )),
+        Method::ImplicitEuler => run!(make_implicit_euler_method_system(create_problem(), h)),
+        Method::RungeKutta(tableau) => run!(make_explicit_runge_kutta_with_tableau(
+            create_problem(),
+            h,
+            tableau.clone()
+        )),
+        Method::Embedded(tableau) => {
+            let error_control = ErrorControl::new(atol, rtol).with_norm(norm);
+            let mut embedded = make_embedded_runge_kutta(create_problem, h, tableau.clone(), 0.0)
+                .with_error_control(error_control);
+            if automatic_h {
+                embedded = embedded.with_automatic_initial_step();
+            }
+            if let Some(controller) = controller {
+                embedded.controller = controller;
+            }
+            embedded.h_min = h_min;
+            embedded.h_max = h_max;
+            embedded.max_steps = max_steps;
+            run!(embedded)
+        }
+        Method::AdamsBashforth2 => run!(make_adams_bashforth_2_method(
+            create_problem,
+            h,
+            make_classic_runge_kutta
+        )),
+        Method::AdamsBashforth3 => run!(make_adams_bashforth_3_method(
+            create_problem,
+            h,
+            make_classic_runge_kutta
+        )),
+        Method::Nystroem3 => run!(make_nystroem_3_method(
+            create_problem,
+            h,
+            make_classic_runge_kutta
+        )),
+        Method::MilneSimpson => run!(make_milne_simpson_method(
+            create_problem,
+            h,
+            make_classic_runge_kutta
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_with_output_times() {
+        let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+        let problem = InitialValueSystemProblem::new(0.0, vec![1.0], rhs);
+        let options = SolverOptions::at(OutputTimes::At(vec![0.5, 1.0]))
+            .event(Event::new(|_t, y: &[f64]| y[0] - 0.5));
+
+        assert!(matches!(
+            solve(problem, &Method::rk4(), options),
+            Err(SolverError::EventsWithOutputTimes)
+        ));
+    }
+}
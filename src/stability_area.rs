@@ -1,29 +1,64 @@
 use crate::definitions::{Interval, Point2D, SampleableFunction};
 use crate::util::make_supporting_points;
-use itertools::iproduct;
 use num::complex::Complex64;
+use std::ops::ControlFlow;
 
 /// Sampling whole area instead of contours, because I'm lazy and don't have that as a builting unlike octave.
+///
+/// `progress` gets the number of samples taken so far and the total number, before the first one
+/// and after every column of the grid. Returning `ControlFlow::Break` stops early with the points found until then.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::Interval;
+/// use ngdl_rust::stability_area::sample_stability_area;
+/// use num::complex::Complex64;
+/// use std::ops::ControlFlow;
+///
+/// // Explicit euler, the disk of radius 1 around -1
+/// let r = |z: Complex64| (z + 1.0).norm();
+/// let mut reported = 0;
+/// let area = sample_stability_area(
+///     r,
+///     100,
+///     Interval::new(-3.0, 1.0),
+///     Interval::new(-2.0, 2.0),
+///     |done, total| {
+///         assert!(done <= total);
+///         reported = done;
+///         ControlFlow::Continue(())
+///     },
+/// );
+///
+/// assert_eq!(reported, 101 * 101);
+/// assert!(area.iter().all(|p| (p.x + 1.0).hypot(p.y) <= 1.0));
+/// ```
 pub fn sample_stability_area<FT: SampleableFunction<Complex64, f64>>(
     f: FT,
     n_samples: usize,
     re_interval: Interval,
     im_interval: Interval,
+    mut progress: impl FnMut(usize, usize) -> ControlFlow<()>,
 ) -> Vec<Point2D> {
-    println!(
-        "Sampling stability area. Projected samples: {}",
-        n_samples * n_samples
-    );
-
     let re_samples = make_supporting_points(n_samples, re_interval);
     let im_samples = make_supporting_points(n_samples, im_interval);
+    let total = re_samples.len() * im_samples.len();
 
-    iproduct!(re_samples, im_samples)
-        .map(|(r, i)| {
-            let z = Complex64::new(r, i);
-            (z, f.value_at(z))
-        })
-        .filter(|(_, v)| *v <= 1.0)
-        .map(|(z, _)| Point2D::new(z.re, z.im))
-        .collect()
+    let mut area = Vec::new();
+    if progress(0, total).is_break() {
+        return area;
+    }
+    for (column, r) in re_samples.iter().enumerate() {
+        area.extend(
+            im_samples
+                .iter()
+                .map(|i| Complex64::new(*r, *i))
+                .filter(|z| f.value_at(*z) <= 1.0)
+                .map(|z| Point2D::new(z.re, z.im)),
+        );
+        if progress((column + 1) * im_samples.len(), total).is_break() {
+            break;
+        }
+    }
+    area
 }
@@ -0,0 +1,82 @@
+use std::fmt::{Display, Error, Formatter};
+
+/// How much work a solver did, to compare methods with each other.
+/// Every solution carries the stats of the run that produced it.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::{InitialValueSystemProblem, ODEMethod};
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+/// let problem = InitialValueSystemProblem::new(0.0, vec![1.0], rhs);
+///
+/// let solution = make_classic_runge_kutta(problem, 0.25).interval(1.0, 0).unwrap();
+///
+/// let stats = solution.stats();
+/// assert_eq!(stats.accepted_steps, 4);
+/// assert_eq!(stats.rhs_evaluations, 16);
+/// assert_eq!(stats.h_max, Some(0.25));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SolverStats {
+    /// Evaluations of the right-hand side
+    pub rhs_evaluations: usize,
+    /// Evaluations of the Jacobian of the right-hand side
+    pub jacobian_evaluations: usize,
+    /// Decompositions of the matrices of the linear systems
+    pub lu_decompositions: usize,
+    /// Iterations of Newton's method over all steps
+    pub newton_iterations: usize,
+    /// Steps that made it into the solution
+    pub accepted_steps: usize,
+    /// Steps an adaptive method threw away because the error was too large
+    pub rejected_steps: usize,
+    /// Smallest accepted step size, None if no step was taken
+    pub h_min: Option<f64>,
+    /// Largest accepted step size, None if no step was taken
+    pub h_max: Option<f64>,
+}
+
+impl SolverStats {
+    /// Records an accepted step of size h, negative if it went backwards.
+    pub fn accept(&mut self, h: f64) {
+        let h = h.abs();
+        self.accepted_steps += 1;
+        self.h_min = Some(self.h_min.map_or(h, |h_min| h_min.min(h)));
+        self.h_max = Some(self.h_max.map_or(h, |h_max| h_max.max(h)));
+    }
+
+    /// Records a rejected step.
+    pub fn reject(&mut self) {
+        self.rejected_steps += 1;
+    }
+
+    /// Adds the evaluations and solves of `other`, but not its steps.
+    /// For work a method does on the side, like the start values of a multistep method.
+    pub fn add_work(&mut self, other: &SolverStats) {
+        self.rhs_evaluations += other.rhs_evaluations;
+        self.jacobian_evaluations += other.jacobian_evaluations;
+        self.lu_decompositions += other.lu_decompositions;
+        self.newton_iterations += other.newton_iterations;
+    }
+}
+
+impl Display for SolverStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "{} accepted, {} rejected steps, {} rhs evaluations, {} Jacobians, {} LU decompositions, {} Newton iterations",
+            self.accepted_steps,
+            self.rejected_steps,
+            self.rhs_evaluations,
+            self.jacobian_evaluations,
+            self.lu_decompositions,
+            self.newton_iterations
+        )?;
+        if let (Some(h_min), Some(h_max)) = (self.h_min, self.h_max) {
+            write!(f, ", h in [{:E}, {:E}]", h_min, h_max)?;
+        }
+        Ok(())
+    }
+}
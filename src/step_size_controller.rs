@@ -0,0 +1,435 @@
+use crate::definitions::{OdeSystem, Scalar};
+use crate::tolerance::ErrorControl;
+use crate::util::directed_step;
+use crate::{abs, powf};
+use derive_new::*;
+
+/// Chooses the next step size of an adaptive method from the error estimates of its tries.
+/// Not tied to explicit methods, anything that estimates its local error can use it.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::InitialValueSystemProblem;
+/// use ngdl_rust::embedded_rk::make_dopri5;
+/// use ngdl_rust::step_size_controller::{ControllerLimits, PiController};
+///
+/// let create_problem = || {
+///     let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| {
+///         dydt[0] = y[1];
+///         dydt[1] = -y[0];
+///     };
+///     InitialValueSystemProblem::new(0.0, vec![1.0, 0.0], rhs)
+/// };
+/// let limits = ControllerLimits::new(0.8, 0.2, 5.0);
+///
+/// let solution = make_dopri5(create_problem, 0.1, 1e-8)
+///     .with_controller(PiController::default().with_limits(limits))
+///     .interval(10.0, 0)
+///     .unwrap();
+///
+/// let (t, y) = (solution.final_time().unwrap(), solution.final_state().unwrap());
+/// assert!((y[0] - t.cos()).abs() < 1e-6);
+/// ```
+pub trait StepSizeController {
+    /// The size of the next try after a try of size h > 0 with the error err,
+    /// which was accepted if err <= 1.
+    /// order is the order of the error estimate, e.g. 4 for DOPRI5.
+    fn next_step_size(&mut self, h: f64, err: f64, order: usize) -> f64;
+
+    /// Forgets the earlier steps, e.g. after the state was changed from outside.
+    fn reset(&mut self) {}
+}
+
+/// Settings all controllers share: h is multiplied with safety times the factor of the controller,
+/// but with at least factor_min and at most factor_max.
+#[derive(Clone, Copy, Debug, PartialEq, new)]
+pub struct ControllerLimits {
+    /// Keeps the next error safely below the tolerance
+    pub safety: f64,
+    /// Smallest factor the step size changes with
+    pub factor_min: f64,
+    /// Largest factor the step size changes with
+    pub factor_max: f64,
+}
+
+impl ControllerLimits {
+    /// h times the clamped factor.
+    fn apply(&self, h: f64, factor: f64) -> f64 {
+        // NaN errors shrink the step as much as allowed
+        h * self
+            .factor_max
+            .min(self.factor_min.max(self.safety * factor))
+    }
+}
+
+/// Safety factor 0.9, the step size changes at most by a factor of 2.
+impl Default for ControllerLimits {
+    fn default() -> Self {
+        ControllerLimits::new(0.9, 0.5, 2.0)
+    }
+}
+
+/// Elementary controller, factor (1/err)^(1/(q+1)) for an error estimate of order q.
+/// The default of the adaptive methods.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IController {
+    limits: ControllerLimits,
+}
+
+impl IController {
+    /// Uses the limits instead of the default ones.
+    pub fn with_limits(mut self, limits: ControllerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl StepSizeController for IController {
+    fn next_step_size(&mut self, h: f64, err: f64, order: usize) -> f64 {
+        let k = order as f64 + 1.0;
+        self.limits.apply(h, powf!(1.0 / err, 1.0 / k))
+    }
+}
+
+/// Smallest error that is remembered, so a step that was by chance almost exact does not dominate the next ones.
+const ERR_MIN: f64 = 1e-4;
+
+/// PI controller, factor err_n^(-alpha/k) err_n-1^(beta/k) with k = q + 1
+/// and err_n-1 the error of the last accepted step, 1 before the first one.
+/// Damps the oscillation between accepted and rejected steps.
+/// Hairer, Wanner: Solving ODEs II, section IV.2
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PiController {
+    limits: ControllerLimits,
+    alpha: f64,
+    beta: f64,
+    err_last: f64,
+}
+
+impl PiController {
+    /// PI controller with the exponents alpha / k and beta / k.
+    pub fn new(alpha: f64, beta: f64) -> Self {
+        PiController {
+            limits: ControllerLimits::default(),
+            alpha,
+            beta,
+            err_last: 1.0,
+        }
+    }
+
+    /// Uses the limits instead of the default ones.
+    pub fn with_limits(mut self, limits: ControllerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// alpha = 0.7, beta = 0.4 as recommended by Hairer and Söderlind.
+impl Default for PiController {
+    fn default() -> Self {
+        PiController::new(0.7, 0.4)
+    }
+}
+
+impl StepSizeController for PiController {
+    fn next_step_size(&mut self, h: f64, err: f64, order: usize) -> f64 {
+        let k = order as f64 + 1.0;
+        let factor = powf!(1.0 / err, self.alpha / k) * powf!(self.err_last, self.beta / k);
+        if err <= 1.0 {
+            self.err_last = err.max(ERR_MIN);
+        }
+        self.limits.apply(h, factor)
+    }
+
+    fn reset(&mut self) {
+        self.err_last = 1.0;
+    }
+}
+
+/// PID controller, factor err_n^(-k1/k) err_n-1^(k2/k) err_n-2^(-k3/k) with k = q + 1,
+/// err_n-1 and err_n-2 are the errors of the last two accepted steps, 1 before the first ones.
+/// Söderlind: Digital filters in adaptive time-stepping
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PidController {
+    limits: ControllerLimits,
+    k1: f64,
+    k2: f64,
+    k3: f64,
+    // Errors of the last two accepted steps, the latest first
+    errs_last: [f64; 2],
+}
+
+impl PidController {
+    /// PID controller with the exponents k1 / k, k2 / k and k3 / k.
+    pub fn new(k1: f64, k2: f64, k3: f64) -> Self {
+        PidController {
+            limits: ControllerLimits::default(),
+            k1,
+            k2,
+            k3,
+            errs_last: [1.0; 2],
+        }
+    }
+
+    /// Uses the limits instead of the default ones.
+    pub fn with_limits(mut self, limits: ControllerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// k1 = 0.58, k2 = 0.21, k3 = 0.1 as in CVODE.
+impl Default for PidController {
+    fn default() -> Self {
+        PidController::new(0.58, 0.21, 0.1)
+    }
+}
+
+impl StepSizeController for PidController {
+    fn next_step_size(&mut self, h: f64, err: f64, order: usize) -> f64 {
+        let k = order as f64 + 1.0;
+        let [err_1, err_2] = self.errs_last;
+        let factor = powf!(1.0 / err, self.k1 / k)
+            * powf!(err_1, self.k2 / k)
+            * powf!(1.0 / err_2, self.k3 / k);
+        if err <= 1.0 {
+            self.errs_last = [err.max(ERR_MIN), err_1];
+        }
+        self.limits.apply(h, factor)
+    }
+
+    fn reset(&mut self) {
+        self.errs_last = [1.0; 2];
+    }
+}
+
+/// Predictive controller of Gustafsson, after an accepted step the smaller of
+/// the elementary proposal and (h_n / h_n-1) (1/err_n)^(1/k) (err_n-1 / err_n)^(1/k) with k = q + 1.
+/// After rejected steps it is the elementary controller.
+/// Hairer, Wanner: Solving ODEs II, section IV.8
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GustafssonController {
+    limits: ControllerLimits,
+    // Size and error of the last accepted step
+    last: Option<(f64, f64)>,
+}
+
+impl GustafssonController {
+    /// Uses the limits instead of the default ones.
+    pub fn with_limits(mut self, limits: ControllerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl StepSizeController for GustafssonController {
+    fn next_step_size(&mut self, h: f64, err: f64, order: usize) -> f64 {
+        let k = order as f64 + 1.0;
+        let elementary = powf!(1.0 / err, 1.0 / k);
+        if err <= 1.0 {
+            let err = err.max(ERR_MIN);
+            let predicted = self.last.map(|(h_last, err_last)| {
+                (h / h_last) * elementary * powf!(err_last / err, 1.0 / k)
+            });
+            self.last = Some((h, err));
+            if let Some(predicted) = predicted {
+                return self.limits.apply(h, elementary.min(predicted));
+            }
+        }
+        self.limits.apply(h, elementary)
+    }
+
+    fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Estimates a good first step size for a method of the given order from t0 towards t_target,
+/// with the algorithm of Hairer, Norsett, Wanner: Solving ODEs I, section II.4.
+/// The sizes of y0, f(t0, y0) and of a trial Euler step are measured with the norm of the error control,
+/// applied to the moduli of the components, so complex states work as well.
+/// Costs two evaluations of the right-hand side, the result is positive and at most |t_target - t0|.
+/// For an empty interval, t_target = t0, there is nothing to step over, so it is zero without evaluating anything.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::step_size_controller::initial_step_size;
+/// use ngdl_rust::tolerance::ErrorControl;
+///
+/// // Fast decay needs small steps
+/// let slow = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+/// let fast = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -1000.0 * y[0];
+/// let control = ErrorControl::new(1e-6, 1e-6);
+///
+/// let h_slow = initial_step_size(&slow, 0.0, &[1.0], 10.0, 4, &control);
+/// let h_fast = initial_step_size(&fast, 0.0, &[1.0], 10.0, 4, &control);
+/// assert!(h_fast < h_slow / 10.0);
+/// ```
+pub fn initial_step_size<N: Scalar, SYS: OdeSystem<N>>(
+    system: &SYS,
+    t0: f64,
+    y0: &[N],
+    t_target: f64,
+    order: usize,
+    error_control: &ErrorControl,
+) -> f64 {
+    if t_target == t0 {
+        return 0.0;
+    }
+    let direction = directed_step(1.0, t0, t_target);
+    let moduli = |v: &[N]| -> Vec<f64> { v.iter().map(|x| x.modulus()).collect() };
+    let y0_moduli = moduli(y0);
+    let norm = |v: &[N]| error_control.scaled_error(&mut moduli(v), &y0_moduli, &y0_moduli);
+
+    let f0 = system.rhs_vec(t0, y0);
+    let d0 = norm(y0);
+    let d1 = norm(&f0);
+    let h0 = if d0 < 1e-5 || d1 < 1e-5 {
+        1e-6
+    } else {
+        0.01 * d0 / d1
+    };
+
+    // One explicit Euler step to estimate the second derivative
+    let y1: Vec<N> = y0
+        .iter()
+        .zip(&f0)
+        .map(|(y, f)| *y + N::from_f64(direction * h0) * *f)
+        .collect();
+    let f1 = system.rhs_vec(t0 + direction * h0, &y1);
+    let df: Vec<N> = f1.iter().zip(&f0).map(|(f1, f0)| *f1 - *f0).collect();
+    let d2 = norm(&df) / h0;
+
+    let d_max = d1.max(d2);
+    let h1 = if d_max <= 1e-15 {
+        (h0 * 1e-3).max(1e-6)
+    } else {
+        powf!(0.01 / d_max, 1.0 / (order as f64 + 1.0))
+    };
+    (100.0 * h0).min(h1).min(abs!(t_target - t0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::complex::Complex64;
+
+    #[test]
+    fn test_i_controller_ratios() {
+        let mut controller = IController::default();
+
+        // (1 / err)^(1/5) for an error estimate of order 4
+        assert!(abs!(controller.next_step_size(1.0, 1.0 / 32.0, 4) - 0.9 * 2.0) < 1e-12);
+        assert!(abs!(controller.next_step_size(0.5, 1.0, 4) - 0.5 * 0.9) < 1e-12);
+        // Clamped to the limits, NaN shrinks as much as allowed
+        assert_eq!(controller.next_step_size(1.0, 1e-20, 4), 2.0);
+        assert_eq!(controller.next_step_size(1.0, 1e20, 4), 0.5);
+        assert_eq!(controller.next_step_size(1.0, f64::NAN, 4), 0.5);
+
+        let limits = ControllerLimits::new(1.0, 0.1, 10.0);
+        let mut controller = IController::default().with_limits(limits);
+        assert!(abs!(controller.next_step_size(1.0, 1e-5, 4) - 10.0) < 1e-12);
+    }
+
+    #[test]
+    fn test_pi_controller_memory() {
+        let mut pi = PiController::new(0.7, 0.4);
+        let mut i = IController::default();
+
+        // Without a history the proposal only depends on err^(-alpha/k)
+        let h = pi.next_step_size(1.0, 0.5, 4);
+        assert!(abs!(h - 0.9 * f64::powf(2.0, 0.7 / 5.0)) < 1e-12);
+        // The small error of the last step damps the growth
+        let h_pi = pi.next_step_size(1.0, 0.5, 4);
+        assert!(h_pi < h);
+        assert!(abs!(h_pi - h * f64::powf(0.5, 0.4 / 5.0)) < 1e-12);
+        // Rejected steps are not remembered
+        pi.next_step_size(1.0, 4.0, 4);
+        assert!(abs!(pi.next_step_size(1.0, 0.5, 4) - h_pi) < 1e-12);
+
+        pi.reset();
+        assert!(abs!(pi.next_step_size(1.0, 0.5, 4) - h) < 1e-12);
+        // alpha = 1, beta = 0 is the elementary controller
+        let mut elementary = PiController::new(1.0, 0.0);
+        assert_eq!(
+            elementary.next_step_size(1.0, 0.3, 4),
+            i.next_step_size(1.0, 0.3, 4)
+        );
+    }
+
+    #[test]
+    fn test_pid_controller_memory() {
+        let mut pid = PidController::new(0.58, 0.21, 0.1);
+        let h = pid.next_step_size(1.0, 0.5, 4);
+        assert!(abs!(h - 0.9 * f64::powf(2.0, 0.58 / 5.0)) < 1e-12);
+
+        let h = pid.next_step_size(1.0, 0.5, 4);
+        let expected = 0.9 * f64::powf(2.0, 0.58 / 5.0) * f64::powf(0.5, 0.21 / 5.0);
+        assert!(abs!(h - expected) < 1e-12);
+
+        let h = pid.next_step_size(1.0, 0.5, 4);
+        let expected = expected * f64::powf(2.0, 0.1 / 5.0);
+        assert!(abs!(h - expected) < 1e-12);
+    }
+
+    #[test]
+    fn test_gustafsson_controller() {
+        let limits = ControllerLimits::new(0.9, 0.1, 10.0);
+        let mut gustafsson = GustafssonController::default().with_limits(limits);
+        let mut i = IController::default().with_limits(limits);
+
+        // The first step has no history
+        assert_eq!(
+            gustafsson.next_step_size(1.0, 0.1, 4),
+            i.next_step_size(1.0, 0.1, 4)
+        );
+        // The error grew although the step shrank, the prediction is below the elementary proposal
+        let h = gustafsson.next_step_size(0.5, 0.9, 4);
+        let predicted = 0.5 * f64::powf(1.0 / 0.9, 0.2) * f64::powf(0.1 / 0.9, 0.2);
+        assert!(h < i.next_step_size(0.5, 0.9, 4));
+        assert!(abs!(h - 0.5 * 0.9 * predicted) < 1e-12);
+        // After a rejection it is elementary again
+        assert_eq!(
+            gustafsson.next_step_size(1.0, 2.0, 4),
+            i.next_step_size(1.0, 2.0, 4)
+        );
+        // Small errors do not let the prediction exceed the elementary proposal
+        gustafsson.reset();
+        gustafsson.next_step_size(1.0, 0.9, 4);
+        assert_eq!(
+            gustafsson.next_step_size(2.0, 0.01, 4),
+            i.next_step_size(2.0, 0.01, 4)
+        );
+    }
+
+    #[test]
+    fn test_initial_step_size_bounds() {
+        let system = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+        let control = ErrorControl::new(1e-6, 1e-6);
+
+        let h_forward = initial_step_size(&system, 0.0, &[1.0], 10.0, 4, &control);
+        let h_backward = initial_step_size(&system, 0.0, &[1.0], -10.0, 4, &control);
+        assert!(h_forward > 0.0 && h_backward > 0.0);
+        // Never longer than the interval
+        assert_eq!(
+            initial_step_size(&system, 0.0, &[1.0], 1e-8, 4, &control),
+            1e-8
+        );
+        assert_eq!(
+            initial_step_size(&system, 0.0, &[1.0], 0.0, 4, &control),
+            0.0
+        );
+
+        // Complex states are measured by their moduli
+        let complex_system = |_t: f64, y: &[Complex64], dydt: &mut [Complex64]| dydt[0] = -y[0];
+        let h_complex = initial_step_size(
+            &complex_system,
+            0.0,
+            &[Complex64::new(1.0, 0.0)],
+            10.0,
+            4,
+            &control,
+        );
+        assert_eq!(h_complex, h_forward);
+    }
+}
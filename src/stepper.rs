@@ -0,0 +1,52 @@
+use crate::definitions::{SolverError, StateVector};
+use crate::stats::SolverStats;
+
+/// Drives a method step by step instead of running it to a target time.
+/// Every call to `next` takes one step and yields the new (t, state), the start value is not yielded.
+/// Between two steps the state can be inspected or replaced and h can be changed,
+/// a negative h steps backwards in time.
+/// The iteration never ends on its own, bound it with e.g. `take_while`.
+/// If a step fails the iteration stops and the reason is available via `error`.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::definitions::InitialValueSystemProblem;
+/// use ngdl_rust::explicit_runge_kutta::make_classic_runge_kutta;
+/// use ngdl_rust::stepper::Stepper;
+///
+/// let rhs = |_t: f64, y: &[f64], dydt: &mut [f64]| dydt[0] = -y[0];
+/// let method = make_classic_runge_kutta(InitialValueSystemProblem::new(0.0, vec![1.0], rhs), 0.1);
+///
+/// let mut stepper = method.stepper().unwrap();
+/// let first_steps: Vec<(f64, Vec<f64>)> = stepper.by_ref().take_while(|(t, _)| *t < 0.5).collect();
+/// assert_eq!(first_steps.len(), 4);
+///
+/// // Kick the state and continue with a finer step size
+/// stepper.set_state(vec![2.0 * stepper.state()[0]]);
+/// stepper.set_h(0.01);
+/// let (t, _) = stepper.next().unwrap();
+/// assert!((t - 0.51).abs() < 1e-12);
+/// assert!(stepper.error().is_none());
+/// ```
+pub trait Stepper<S: StateVector = Vec<f64>>: Iterator<Item = (f64, S)> {
+    /// Time of the current state
+    fn t(&self) -> f64;
+
+    /// The current state
+    fn state(&self) -> &S;
+
+    /// Replaces the current state, the next step starts from it.
+    fn set_state(&mut self, values: S);
+
+    /// Step size of the next step
+    fn h(&self) -> f64;
+
+    /// Changes the step size of the following steps.
+    fn set_h(&mut self, h: f64);
+
+    /// The reason the iteration stopped, if a step failed.
+    fn error(&self) -> Option<SolverError>;
+
+    /// The work done by all steps so far.
+    fn stats(&self) -> SolverStats;
+}
@@ -0,0 +1,190 @@
+use crate::definitions::SolverError;
+use crate::{abs, sqrt};
+
+/// An absolute or relative tolerance, either the same for all components or one per component.
+/// Converts from a number and from a vector, so both can be passed where a tolerance is expected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tolerance {
+    /// The same tolerance for all components
+    Scalar(f64),
+    /// One tolerance per component
+    PerComponent(Vec<f64>),
+}
+
+impl Tolerance {
+    /// The tolerance of the component.
+    pub fn get(&self, component: usize) -> f64 {
+        match self {
+            Tolerance::Scalar(tolerance) => *tolerance,
+            Tolerance::PerComponent(tolerances) => tolerances[component],
+        }
+    }
+}
+
+impl From<f64> for Tolerance {
+    fn from(tolerance: f64) -> Self {
+        Tolerance::Scalar(tolerance)
+    }
+}
+
+impl From<Vec<f64>> for Tolerance {
+    fn from(tolerances: Vec<f64>) -> Self {
+        Tolerance::PerComponent(tolerances)
+    }
+}
+
+impl From<&[f64]> for Tolerance {
+    fn from(tolerances: &[f64]) -> Self {
+        Tolerance::PerComponent(tolerances.to_vec())
+    }
+}
+
+/// A user supplied norm of the scaled errors.
+pub type NormFunction = Box<dyn Fn(&[f64]) -> f64>;
+
+/// Combines the scaled errors e_i / sc_i of the components into one number, a step is accepted if it is at most 1.
+pub enum ErrorNorm {
+    /// sqrt(1/n sum (e_i / sc_i)^2) as in Hairer, Norsett, Wanner: Solving ODEs I, section II.4
+    Rms,
+    /// max |e_i / sc_i|
+    Max,
+    /// Any other norm of the scaled errors
+    Custom(NormFunction),
+}
+
+impl ErrorNorm {
+    /// The norm of the scaled errors.
+    pub fn norm(&self, scaled_errors: &[f64]) -> f64 {
+        match self {
+            ErrorNorm::Rms => sqrt!(
+                scaled_errors.iter().map(|e| e * e).sum::<f64>()
+                    / scaled_errors.len().max(1) as f64
+            ),
+            ErrorNorm::Max => scaled_errors
+                .iter()
+                .fold(0.0, |max, e| f64::max(max, abs!(e))),
+            ErrorNorm::Custom(norm) => norm(scaled_errors),
+        }
+    }
+}
+
+/// Decides with tolerances and a norm whether the error estimate of a step is small enough.
+/// Component i is scaled by sc_i = atol_i + rtol_i max(|y_i|, |ŷ_i|),
+/// where y is the state before the step and ŷ the one after it.
+///
+/// # Example
+/// ```
+/// use ngdl_rust::tolerance::{ErrorControl, ErrorNorm};
+///
+/// // The second component is a million times smaller than the first
+/// let control = ErrorControl::new(vec![1e-3, 1e-9], 0.0).with_norm(ErrorNorm::Max);
+/// let mut errors = vec![1e-4, 1e-10];
+///
+/// assert!((control.scaled_error(&mut errors, &[1.0, 1e-6], &[1.0, 1e-6]) - 0.1).abs() < 1e-12);
+/// ```
+pub struct ErrorControl {
+    atol: Tolerance,
+    rtol: Tolerance,
+    norm: ErrorNorm,
+}
+
+impl ErrorControl {
+    /// Error control with the Hairer RMS norm.
+    pub fn new<A: Into<Tolerance>, R: Into<Tolerance>>(atol: A, rtol: R) -> Self {
+        ErrorControl {
+            atol: atol.into(),
+            rtol: rtol.into(),
+            norm: ErrorNorm::Rms,
+        }
+    }
+
+    /// Uses the norm instead of the RMS norm.
+    pub fn with_norm(mut self, norm: ErrorNorm) -> Self {
+        self.norm = norm;
+        self
+    }
+
+    /// Fails with `SolverError::DimensionMismatch` if a per-component tolerance does not have dim components.
+    pub(crate) fn check_dimension(&self, dim: usize) -> Result<(), SolverError> {
+        for tolerance in [&self.atol, &self.rtol] {
+            if let Tolerance::PerComponent(tolerances) = tolerance {
+                if tolerances.len() != dim {
+                    return Err(SolverError::DimensionMismatch {
+                        expected: dim,
+                        found: tolerances.len(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Scales the errors of the step from values to next_values in place and returns their norm.
+    pub fn scaled_error(&self, errors: &mut [f64], values: &[f64], next_values: &[f64]) -> f64 {
+        for (idx, (e, (y, y_next))) in errors
+            .iter_mut()
+            .zip(values.iter().zip(next_values))
+            .enumerate()
+        {
+            let sc = self.atol.get(idx) + self.rtol.get(idx) * abs!(y).max(abs!(y_next));
+            *e /= sc;
+        }
+        self.norm.norm(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaling() {
+        // sc = atol + rtol max(|y|, |ŷ|) = 1e-3 + 1e-2 * 2 for the first component
+        let control = ErrorControl::new(1e-3, 1e-2).with_norm(ErrorNorm::Max);
+        let mut errors = vec![0.021, 0.0];
+
+        assert!(abs!(control.scaled_error(&mut errors, &[1.0, 0.0], &[-2.0, 0.0]) - 1.0) < 1e-12);
+        assert!(abs!(errors[0] - 1.0) < 1e-12);
+    }
+
+    #[test]
+    fn test_per_component() {
+        let control = ErrorControl::new(vec![1.0, 1e-6], vec![0.0, 0.5]).with_norm(ErrorNorm::Max);
+        let mut errors = vec![0.5, 1e-6];
+
+        // The second component is scaled with 1e-6 + 0.5 * 2
+        let err = control.scaled_error(&mut errors, &[0.0, 2.0], &[0.0, 1.0]);
+        assert!(abs!(err - 0.5) < 1e-12);
+        assert!(abs!(errors[1] - 1e-6 / (1e-6 + 1.0)) < 1e-15);
+
+        assert_eq!(Tolerance::from(&[1.0, 2.0][..]).get(1), 2.0);
+        assert_eq!(Tolerance::from(3.0).get(7), 3.0);
+    }
+
+    #[test]
+    fn test_norms() {
+        let errors = [3.0, -4.0];
+
+        assert!(abs!(ErrorNorm::Rms.norm(&errors) - (12.5f64).sqrt()) < 1e-12);
+        assert_eq!(ErrorNorm::Max.norm(&errors), 4.0);
+        let l1 = ErrorNorm::Custom(Box::new(|e: &[f64]| e.iter().map(|e| e.abs()).sum()));
+        assert_eq!(l1.norm(&errors), 7.0);
+        // Nothing to measure
+        assert_eq!(ErrorNorm::Rms.norm(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_check_dimension() {
+        let control = ErrorControl::new(1e-6, vec![1e-6; 3]);
+
+        assert!(control.check_dimension(3).is_ok());
+        assert!(matches!(
+            control.check_dimension(2),
+            Err(SolverError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        ));
+        assert!(ErrorControl::new(1e-6, 1e-6).check_dimension(5).is_ok());
+    }
+}
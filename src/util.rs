@@ -84,6 +84,21 @@ pub fn sse(v1: &[Point2D], v2: &[Point2D]) -> f64 {
         .sum()
 }
 
+/// The step size h with the sign pointing from start_time towards t_target,
+/// so the solvers can integrate backwards in time as well.
+pub fn directed_step(h: f64, start_time: f64, t_target: f64) -> f64 {
+    if t_target < start_time {
+        -h.abs()
+    } else {
+        h.abs()
+    }
+}
+
+/// True if t has not yet reached t_target when moving in the direction of h.
+pub fn before_target(t: f64, t_target: f64, h: f64) -> bool {
+    (t_target - t) * h > 0.0
+}
+
 /// x!
 #[macro_export]
 macro_rules! fac {